@@ -0,0 +1,44 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use ruplicity::backend::local::LocalBackend;
+use ruplicity::backend::Backend;
+use ruplicity::collections::Collections;
+use ruplicity::signatures::Chain;
+
+fn single_vol() -> (LocalBackend, Collections) {
+    let backend = LocalBackend::new("tests/backups/single_vol");
+    let coll = Collections::from_filenames(backend.file_names().unwrap());
+    (backend, coll)
+}
+
+fn load_chain(c: &mut Criterion) {
+    let (backend, coll) = single_vol();
+    let sigchain = coll.signature_chains().next().unwrap();
+    c.bench_function("load single_vol chain", |b| {
+        b.iter(|| Chain::from_sigchain(sigchain, &backend).unwrap())
+    });
+}
+
+fn iterate_entries(c: &mut Criterion) {
+    let (backend, coll) = single_vol();
+    let chain = Chain::from_sigchain(coll.signature_chains().next().unwrap(), &backend).unwrap();
+    c.bench_function("iterate single_vol entries", |b| {
+        b.iter(|| chain.snapshots().map(|s| s.files().count()).sum::<usize>())
+    });
+}
+
+fn find_by_path(c: &mut Criterion) {
+    let (backend, coll) = single_vol();
+    let chain = Chain::from_sigchain(coll.signature_chains().next().unwrap(), &backend).unwrap();
+    c.bench_function("find path in single_vol entries", |b| {
+        b.iter(|| {
+            chain
+                .snapshots()
+                .filter_map(|s| s.files().find(|e| e.path_bytes() == b"regular_file"))
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, load_chain, iterate_entries, find_by_path);
+criterion_main!(benches);
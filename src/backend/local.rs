@@ -23,11 +23,14 @@
 //! }
 //! ```
 
-use super::Backend;
+use super::{Backend, BackupFileMetadata};
+use crate::collections::FileNameParser;
 use std::ffi::OsString;
 use std::fs::{self, File};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::Timespec;
 
 /// Backend for some directory in the local file system.
 #[derive(Debug)]
@@ -45,6 +48,37 @@ impl LocalBackend {
             base_path: path.as_ref().to_path_buf(),
         }
     }
+
+    /// Returns all the files in the backend directory, sorted by path.
+    ///
+    /// Unlike `file_names`, the paths include the base path of the backend, and entries that
+    /// cannot be read cause an error.
+    pub fn list_all_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(self.base_path.as_path())? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Returns the files in the backend directory not recognized as duplicity files.
+    ///
+    /// The paths include the base path of the backend, as in `list_all_files`.
+    pub fn list_non_backup_files(&self) -> io::Result<Vec<PathBuf>> {
+        let parser = FileNameParser::new();
+        let mut files = self.list_all_files()?;
+        files.retain(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| parser.parse(name))
+                .is_none()
+        });
+        Ok(files)
+    }
 }
 
 impl Backend for LocalBackend {
@@ -62,6 +96,41 @@ impl Backend for LocalBackend {
         path.push(name);
         File::open(path)
     }
+
+    fn file_names_with_meta(
+        &self,
+    ) -> io::Result<impl Iterator<Item = (OsString, Option<SystemTime>)>> {
+        let files = fs::read_dir(self.base_path.as_path())?
+            .flatten()
+            .map(|entry| {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                (entry.file_name(), modified)
+            });
+        Ok(files)
+    }
+
+    fn exists(&self, name: &Path) -> io::Result<bool> {
+        let mut path = self.base_path.clone();
+        path.push(name);
+        Ok(path.exists())
+    }
+
+    fn file_metadata(&self, name: &Path) -> io::Result<BackupFileMetadata> {
+        let mut path = self.base_path.clone();
+        path.push(name);
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok().and_then(to_timespec);
+        Ok(BackupFileMetadata {
+            size_bytes: Some(metadata.len()),
+            modified,
+        })
+    }
+}
+
+fn to_timespec(time: SystemTime) -> Option<Timespec> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| Timespec::new(d.as_secs() as i64, d.subsec_nanos() as i32))
 }
 
 impl Iterator for FileNameIterator {
@@ -111,4 +180,71 @@ mod test {
         ];
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn list_all_files() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let files = backend.list_all_files().unwrap();
+        assert_eq!(files.len(), 9);
+        assert!(files
+            .iter()
+            .all(|f| f.starts_with("tests/backups/single_vol")));
+        assert!(files.contains(&PathBuf::from(
+            "tests/backups/single_vol/duplicity-full.20150617T182545Z.manifest"
+        )));
+        // directories are not listed
+        assert!(!files.contains(&PathBuf::from("tests/backups/single_vol/info")));
+        let backend = LocalBackend::new("tests/backend");
+        assert_eq!(
+            backend.list_all_files().unwrap(),
+            vec![
+                PathBuf::from("tests/backend/file1"),
+                PathBuf::from("tests/backend/file2")
+            ]
+        );
+        assert!(LocalBackend::new("tests/missing").list_all_files().is_err());
+    }
+
+    #[test]
+    fn list_non_backup_files() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        assert!(backend.list_non_backup_files().unwrap().is_empty());
+        let backend = LocalBackend::new("tests/backups/single_vol/info");
+        assert_eq!(
+            backend.list_non_backup_files().unwrap(),
+            backend.list_all_files().unwrap()
+        );
+        assert_eq!(backend.list_non_backup_files().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn file_metadata() {
+        let backend = LocalBackend::new("tests/backend");
+        let metadata = backend.file_metadata(Path::new("file1")).unwrap();
+        assert_eq!(metadata.size_bytes, Some(4));
+        assert!(metadata.modified.is_some());
+        assert!(backend.file_metadata(Path::new("missing")).is_err());
+    }
+
+    #[test]
+    fn file_names_with_meta() {
+        let backend = LocalBackend::new("tests/backend");
+        let mut files = backend.file_names_with_meta().unwrap().collect::<Vec<_>>();
+        files.sort();
+        let names = files
+            .iter()
+            .map(|f| f.0.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["file1", "file2"]);
+        assert!(files.iter().all(|f| f.1.is_some()));
+        let modified = fs::metadata("tests/backend/file1").unwrap().modified().ok();
+        assert_eq!(files[0].1, modified);
+    }
+
+    #[test]
+    fn exists() {
+        let backend = LocalBackend::new("tests/backend");
+        assert!(backend.exists(Path::new("file1")).unwrap());
+        assert!(!backend.exists(Path::new("missing")).unwrap());
+    }
 }
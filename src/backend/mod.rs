@@ -6,8 +6,23 @@
 
 pub mod local;
 
+use std::ffi::OsString;
 use std::io::{self, Read};
 use std::path::Path;
+use std::time::SystemTime;
+
+use time::Timespec;
+
+/// Metadata of a backup file.
+///
+/// The fields are `None` if the backend is not able to provide that information.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BackupFileMetadata {
+    /// The file size in bytes.
+    pub size_bytes: Option<u64>,
+    /// The last modification time.
+    pub modified: Option<Timespec>,
+}
 
 /// A trait used to provide a transport layer for backup files.
 pub trait Backend {
@@ -27,4 +42,273 @@ pub trait Backend {
 
     /// Opens a file for reading.
     fn open_file(&self, name: &Path) -> io::Result<Self::FileStream>;
+
+    /// Returns the available file names, together with their last modification time.
+    ///
+    /// The modification times can be used to detect changes in the backup files. The default
+    /// implementation returns the names from `file_names`, without modification times.
+    fn file_names_with_meta(
+        &self,
+    ) -> io::Result<impl Iterator<Item = (Self::FileName, Option<SystemTime>)>> {
+        Ok(self.file_names()?.into_iter().map(|f| (f, None)))
+    }
+
+    /// Returns whether a file exists.
+    ///
+    /// The default implementation tries to open the file.
+    fn exists(&self, name: &Path) -> io::Result<bool> {
+        match self.open_file(name) {
+            Ok(_) => Ok(true),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the metadata of a file.
+    ///
+    /// The default implementation does not provide any information.
+    fn file_metadata(&self, _name: &Path) -> io::Result<BackupFileMetadata> {
+        Ok(BackupFileMetadata {
+            size_bytes: None,
+            modified: None,
+        })
+    }
+}
+
+/// Decrypts the encrypted files of a backup.
+///
+/// Duplicity encrypts backup files with GnuPG, and adds a `.gpg` extension to their names. This
+/// library does not implement GnuPG: a decryptor, e.g. running the `gpg` program, can be set
+/// with `BackupBuilder::decryptor`, and it is used to read encrypted signatures, manifests and
+/// volumes.
+pub trait Decryptor {
+    /// Decrypts the content of an encrypted file.
+    ///
+    /// The input is borrowed only for the duration of the call, so it must be consumed before
+    /// returning. The returned stream yields the decrypted content.
+    fn decrypt(&self, input: &mut dyn Read) -> io::Result<Box<dyn Read>>;
+}
+
+/// An object safe version of the `Backend` trait.
+///
+/// Every `Backend` implements this trait, so different backends can be stored behind a
+/// `Box<dyn DynBackend>`, that in turn implements `Backend` and can be used by `Backup`.
+///
+/// # Examples
+/// ```
+/// use ruplicity::Backup;
+/// use ruplicity::backend::DynBackend;
+/// use ruplicity::backend::local::LocalBackend;
+///
+/// let backend: Box<dyn DynBackend> = Box::new(LocalBackend::new("tests/backups/single_vol"));
+/// let backup = Backup::new(backend).unwrap();
+/// assert_eq!(backup.snapshots().unwrap().into_iter().count(), 3);
+/// ```
+pub trait DynBackend {
+    /// Returns a list of available file names.
+    ///
+    /// File names that are not valid UTF-8 are converted lossily.
+    fn file_names(&self) -> io::Result<Vec<String>>;
+
+    /// Opens a file for reading.
+    fn open_file(&self, name: &Path) -> io::Result<Box<dyn Read>>;
+}
+
+impl<B> DynBackend for B
+where
+    B: Backend,
+    B::FileStream: 'static,
+{
+    fn file_names(&self) -> io::Result<Vec<String>> {
+        Ok(Backend::file_names(self)?
+            .into_iter()
+            .map(|f| f.as_ref().to_string_lossy().into_owned())
+            .collect())
+    }
+
+    fn open_file(&self, name: &Path) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(Backend::open_file(self, name)?))
+    }
+}
+
+impl Backend for Box<dyn DynBackend> {
+    type FileName = String;
+    type FileNameIter = Vec<String>;
+    type FileStream = Box<dyn Read>;
+
+    fn file_names(&self) -> io::Result<Self::FileNameIter> {
+        (**self).file_names()
+    }
+
+    fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+        (**self).open_file(name)
+    }
+}
+
+/// A backend chosen at runtime, by using `open`.
+///
+/// The associated types of the inner backends are erased, so that all of them can be used
+/// through the same type.
+#[derive(Debug)]
+pub enum AnyBackend {
+    /// A directory in the local file system.
+    Local(local::LocalBackend),
+}
+
+/// Opens the backend corresponding to the scheme of the given URL.
+///
+/// The supported schemes are:
+///
+/// * `file://`, followed by a path in the local file system. A URL without scheme is considered
+///   a local path as well.
+///
+/// # Errors
+/// An error of kind `Unsupported` is returned if the scheme is not supported.
+///
+/// # Examples
+/// ```
+/// use ruplicity::Backup;
+/// use ruplicity::backend;
+///
+/// let backend = backend::open("file://tests/backups/single_vol").unwrap();
+/// let backup = Backup::new(backend).unwrap();
+/// assert_eq!(backup.snapshots().unwrap().into_iter().count(), 3);
+/// ```
+pub fn open(url: &str) -> io::Result<AnyBackend> {
+    let (scheme, path) = match url.find("://") {
+        Some(pos) => (&url[..pos], &url[pos + 3..]),
+        None => ("file", url),
+    };
+    match scheme {
+        "file" => Ok(AnyBackend::Local(local::LocalBackend::new(path))),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported backend scheme '{}'", scheme),
+        )),
+    }
+}
+
+impl Backend for AnyBackend {
+    type FileName = OsString;
+    type FileNameIter = Box<dyn Iterator<Item = OsString>>;
+    type FileStream = Box<dyn Read>;
+
+    fn file_names(&self) -> io::Result<Self::FileNameIter> {
+        match *self {
+            AnyBackend::Local(ref b) => Ok(Box::new(Backend::file_names(b)?)),
+        }
+    }
+
+    fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+        match *self {
+            AnyBackend::Local(ref b) => Ok(Box::new(Backend::open_file(b, name)?)),
+        }
+    }
+
+    fn file_names_with_meta(
+        &self,
+    ) -> io::Result<impl Iterator<Item = (OsString, Option<SystemTime>)>> {
+        match *self {
+            AnyBackend::Local(ref b) => b.file_names_with_meta(),
+        }
+    }
+
+    fn exists(&self, name: &Path) -> io::Result<bool> {
+        match *self {
+            AnyBackend::Local(ref b) => b.exists(name),
+        }
+    }
+
+    fn file_metadata(&self, name: &Path) -> io::Result<BackupFileMetadata> {
+        match *self {
+            AnyBackend::Local(ref b) => b.file_metadata(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::local::LocalBackend;
+    use super::{open, Backend};
+    use std::io::{self, Read};
+    use std::path::Path;
+
+    // uses the default implementations of the optional methods
+    struct DefaultBackend(LocalBackend);
+
+    impl Backend for DefaultBackend {
+        type FileName = <LocalBackend as Backend>::FileName;
+        type FileNameIter = <LocalBackend as Backend>::FileNameIter;
+        type FileStream = <LocalBackend as Backend>::FileStream;
+
+        fn file_names(&self) -> io::Result<Self::FileNameIter> {
+            self.0.file_names()
+        }
+
+        fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+            self.0.open_file(name)
+        }
+    }
+
+    #[test]
+    fn dyn_backends() {
+        use super::DynBackend;
+
+        let backends: Vec<Box<dyn DynBackend>> = vec![
+            Box::new(LocalBackend::new("tests/backend")),
+            Box::new(DefaultBackend(LocalBackend::new("tests/backend"))),
+            Box::new(open("tests/backend").unwrap()),
+        ];
+        for backend in &backends {
+            let mut names = Backend::file_names(backend).unwrap();
+            names.sort();
+            assert_eq!(names, vec!["file1", "file2"]);
+            let mut content = String::new();
+            Backend::open_file(backend, Path::new("file1"))
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content, "aaa\n");
+            assert!(backend.exists(Path::new("file2")).unwrap());
+        }
+    }
+
+    #[test]
+    fn default_exists() {
+        let backend = DefaultBackend(LocalBackend::new("tests/backend"));
+        assert!(backend.exists(Path::new("file1")).unwrap());
+        assert!(!backend.exists(Path::new("missing")).unwrap());
+    }
+
+    #[test]
+    fn default_file_names_with_meta() {
+        let backend = DefaultBackend(LocalBackend::new("tests/backend"));
+        let files = backend.file_names_with_meta().unwrap().collect::<Vec<_>>();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.1.is_none()));
+    }
+
+    #[test]
+    fn open_file_scheme() {
+        for url in &["file://tests/backend", "tests/backend"] {
+            let backend = open(url).unwrap();
+            let mut names = backend.file_names().unwrap().collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, vec!["file1", "file2"]);
+            let mut content = String::new();
+            backend
+                .open_file(Path::new("file1"))
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content, "aaa\n");
+            assert!(backend.exists(Path::new("file2")).unwrap());
+        }
+    }
+
+    #[test]
+    fn open_unsupported_scheme() {
+        let err = open("s3://bucket/path").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
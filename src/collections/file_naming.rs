@@ -1,52 +1,88 @@
+use std::fmt::{Display, Error, Formatter};
+use std::path::Path;
+
+#[cfg(feature = "regex")]
 use regex::Regex;
 use time::Timespec;
 
-use crate::timefmt::parse_time_str;
+use crate::compression::Compression;
+use crate::timefmt::{parse_time_str, SortableTimeDisplay};
 
 pub struct FileNameInfo<'a> {
     pub file_name: &'a str,
     pub info: Info,
 }
 
+/// Information about a backup file, obtained from its name.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Info {
+    /// The type of the file.
     pub tp: Type,
-    pub compressed: bool,
+    /// The compression format of the file.
+    pub compression: Compression,
+    /// Determine if the file is encrypted or not.
     pub encrypted: bool,
 }
 
+/// The type of a backup file.
 #[allow(missing_copy_implementations)]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Type {
+    /// A volume of a full backup.
     Full {
+        /// The backup time.
         time: Timespec,
+        /// The number of the volume, starting from one.
         volume_number: usize,
     },
+    /// The manifest of a full backup.
     FullManifest {
+        /// The backup time.
         time: Timespec,
+        /// Whether the manifest is partial.
         partial: bool,
     },
+    /// A volume of an incremental backup.
     Inc {
+        /// The time of the previous backup.
         start_time: Timespec,
+        /// The backup time.
         end_time: Timespec,
+        /// The number of the volume, starting from one.
         volume_number: usize,
     },
+    /// The manifest of an incremental backup.
     IncManifest {
+        /// The time of the previous backup.
         start_time: Timespec,
+        /// The backup time.
         end_time: Timespec,
+        /// Whether the manifest is partial.
         partial: bool,
     },
+    /// The signatures of a full backup.
     FullSig {
+        /// The backup time.
         time: Timespec,
+        /// Whether the signatures are partial.
         partial: bool,
     },
+    /// The signatures of an incremental backup.
     NewSig {
+        /// The time of the previous backup.
         start_time: Timespec,
+        /// The backup time.
         end_time: Timespec,
+        /// Whether the signatures are partial.
         partial: bool,
     },
 }
 
+/// A parser for backup file names.
+///
+/// This parser uses regular expressions. If the `regex` feature is disabled, this is an alias
+/// for `PlainFileNameParser`.
+#[cfg(feature = "regex")]
 pub struct FileNameParser {
     full_vol_re: Regex,
     full_manifest_re: Regex,
@@ -56,6 +92,16 @@ pub struct FileNameParser {
     new_sig_re: Regex,
 }
 
+/// A parser for backup file names.
+#[cfg(not(feature = "regex"))]
+pub type FileNameParser = PlainFileNameParser;
+
+/// A parser for backup file names that does not depend on regular expressions.
+///
+/// It recognizes the same file names of `FileNameParser`, producing identical results.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PlainFileNameParser;
+
 impl<'a> FileNameInfo<'a> {
     pub fn new(name: &'a str, info: Info) -> Self {
         FileNameInfo {
@@ -69,7 +115,22 @@ impl<'a> FileNameInfo<'a> {
     }
 }
 
+impl Display for Info {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", self.tp)?;
+        match (self.compression.is_compressed(), self.encrypted) {
+            (true, true) => write!(f, " ({}, encrypted)", self.compression),
+            (true, false) => write!(f, " ({})", self.compression),
+            (false, true) => write!(f, " (encrypted)"),
+            (false, false) => Ok(()),
+        }
+    }
+}
+
 impl Type {
+    /// Returns the time span covered by the file.
+    ///
+    /// For full backup files, the start and end times are the same.
     pub fn time_range(&self) -> (Timespec, Timespec) {
         match *self {
             Type::Full { time, .. }
@@ -94,7 +155,84 @@ impl Type {
     }
 }
 
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let partial = match *self {
+            Type::Full {
+                time,
+                volume_number,
+            } => {
+                write!(
+                    f,
+                    "Full backup volume {} at {}",
+                    volume_number,
+                    time.into_sortable_display()
+                )?;
+                false
+            }
+            Type::FullManifest { time, partial } => {
+                write!(
+                    f,
+                    "Full backup manifest at {}",
+                    time.into_sortable_display()
+                )?;
+                partial
+            }
+            Type::Inc {
+                start_time,
+                end_time,
+                volume_number,
+            } => {
+                write!(
+                    f,
+                    "Incremental backup volume {} from {} to {}",
+                    volume_number,
+                    start_time.into_sortable_display(),
+                    end_time.into_sortable_display()
+                )?;
+                false
+            }
+            Type::IncManifest {
+                start_time,
+                end_time,
+                partial,
+            } => {
+                write!(
+                    f,
+                    "Incremental backup manifest from {} to {}",
+                    start_time.into_sortable_display(),
+                    end_time.into_sortable_display()
+                )?;
+                partial
+            }
+            Type::FullSig { time, partial } => {
+                write!(f, "Full signatures at {}", time.into_sortable_display())?;
+                partial
+            }
+            Type::NewSig {
+                start_time,
+                end_time,
+                partial,
+            } => {
+                write!(
+                    f,
+                    "Incremental signatures from {} to {}",
+                    start_time.into_sortable_display(),
+                    end_time.into_sortable_display()
+                )?;
+                partial
+            }
+        };
+        if partial {
+            write!(f, " (partial)")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "regex")]
 impl FileNameParser {
+    /// Creates a new parser.
     pub fn new() -> Self {
         FileNameParser {
             full_vol_re: Regex::new(r"^duplicity-full\.(?P<time>.*?)\.vol(?P<num>[0-9]+)\.difftar(?P<partial>(\.part))?($|\.)").unwrap(),
@@ -106,17 +244,32 @@ impl FileNameParser {
         }
     }
 
+    /// Parses a file name, returning its information.
+    ///
+    /// The file name must not contain the base path. Returns `None` if the file name does not
+    /// belong to a duplicity backup.
     pub fn parse(&self, filename: &str) -> Option<Info> {
         let lower_fname = filename.to_ascii_lowercase();
         let opt_type = self
             .check_full(&lower_fname)
             .or(self.check_inc(&lower_fname))
             .or(self.check_sig(&lower_fname));
-        opt_type.map(|t| Info {
-            tp: t,
-            compressed: is_compressed(lower_fname.as_ref()),
-            encrypted: is_encrypted(lower_fname.as_ref()),
-        })
+        opt_type.map(|t| make_info(t, &lower_fname))
+    }
+
+    /// Parses the file name of a full path, returning its information and the path relative to
+    /// the given base path.
+    ///
+    /// This is useful to parse paths returned by `std::fs::read_dir`. Returns `None` if the path
+    /// is not inside the base path, or if the relative path does not belong to a duplicity
+    /// backup.
+    pub fn parse_with_base_path<'p>(
+        &self,
+        full_path: &'p Path,
+        base: &Path,
+    ) -> Option<(Info, &'p Path)> {
+        let relative = full_path.strip_prefix(base).ok()?;
+        self.parse(relative.to_str()?).map(|info| (info, relative))
     }
 
     fn check_full(&self, filename: &str) -> Option<Type> {
@@ -182,18 +335,197 @@ impl FileNameParser {
     }
 }
 
+#[cfg(feature = "regex")]
+impl Default for FileNameParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlainFileNameParser {
+    /// Creates a new parser.
+    pub fn new() -> Self {
+        PlainFileNameParser
+    }
+
+    /// Parses a file name, returning its information.
+    ///
+    /// The file name must not contain the base path. Returns `None` if the file name does not
+    /// belong to a duplicity backup.
+    pub fn parse(&self, filename: &str) -> Option<Info> {
+        let lower_fname = filename.to_ascii_lowercase();
+        let opt_type = if let Some(rest) = lower_fname.strip_prefix("duplicity-full.") {
+            parse_full(rest)
+        } else if let Some(rest) = lower_fname.strip_prefix("duplicity-inc.") {
+            parse_inc(rest)
+        } else if let Some(rest) = lower_fname.strip_prefix("duplicity-full-signatures.") {
+            parse_full_sig(rest)
+        } else if let Some(rest) = lower_fname.strip_prefix("duplicity-new-signatures.") {
+            parse_new_sig(rest)
+        } else {
+            None
+        };
+        opt_type.map(|t| make_info(t, &lower_fname))
+    }
+
+    /// Parses the file name of a full path, returning its information and the path relative to
+    /// the given base path.
+    ///
+    /// See `FileNameParser::parse_with_base_path`.
+    pub fn parse_with_base_path<'p>(
+        &self,
+        full_path: &'p Path,
+        base: &Path,
+    ) -> Option<(Info, &'p Path)> {
+        let relative = full_path.strip_prefix(base).ok()?;
+        self.parse(relative.to_str()?).map(|info| (info, relative))
+    }
+}
+
+// The following functions mimic the regular expressions used by `FileNameParser`. Times are
+// matched lazily, so the first separator for which the rest of the name matches is taken.
+
+fn parse_full(rest: &str) -> Option<Type> {
+    if let Some((time, num)) = split_lazy(rest, ".vol", volume_suffix) {
+        Some(Type::Full {
+            time: parse_time_str(time)?,
+            volume_number: get_vol_num(num)?,
+        })
+    } else if let Some((time, partial)) = split_lazy(rest, ".manifest", partial_suffix) {
+        Some(Type::FullManifest {
+            time: parse_time_str(time)?,
+            partial,
+        })
+    } else {
+        None
+    }
+}
+
+fn parse_inc(rest: &str) -> Option<Type> {
+    let vol = split_lazy(rest, ".to.", |r| split_lazy(r, ".vol", volume_suffix));
+    if let Some((start_time, (end_time, num))) = vol {
+        return Some(Type::Inc {
+            start_time: parse_time_str(start_time)?,
+            end_time: parse_time_str(end_time)?,
+            volume_number: get_vol_num(num)?,
+        });
+    }
+    let manifest = split_lazy(rest, ".to.", |r| split_lazy(r, ".manifest", partial_suffix));
+    if let Some((start_time, (end_time, partial))) = manifest {
+        return Some(Type::IncManifest {
+            start_time: parse_time_str(start_time)?,
+            end_time: parse_time_str(end_time)?,
+            partial,
+        });
+    }
+    None
+}
+
+fn parse_full_sig(rest: &str) -> Option<Type> {
+    let (time, partial) = split_lazy(rest, ".sigtar", partial_suffix)?;
+    Some(Type::FullSig {
+        time: parse_time_str(time)?,
+        partial,
+    })
+}
+
+fn parse_new_sig(rest: &str) -> Option<Type> {
+    let sig = split_lazy(rest, ".to.", |r| split_lazy(r, ".sigtar", partial_suffix));
+    let (start_time, (end_time, partial)) = sig?;
+    Some(Type::NewSig {
+        start_time: parse_time_str(start_time)?,
+        end_time: parse_time_str(end_time)?,
+        partial,
+    })
+}
+
+/// Splits the string at the first occurrence of the separator for which the remaining part is
+/// accepted by the given function.
+///
+/// The separator must start with an ASCII character.
+fn split_lazy<'a, T, F>(s: &'a str, sep: &str, mut f: F) -> Option<(&'a str, T)>
+where
+    F: FnMut(&'a str) -> Option<T>,
+{
+    let mut start = 0;
+    while let Some(pos) = s[start..].find(sep) {
+        let idx = start + pos;
+        if let Some(res) = f(&s[idx + sep.len()..]) {
+            return Some((&s[..idx], res));
+        }
+        start = idx + 1;
+    }
+    None
+}
+
+/// Matches `[0-9]+\.difftar($|\.)`, returning the volume number.
+fn volume_suffix(s: &str) -> Option<&str> {
+    let digits = s.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 && is_end(s[digits..].strip_prefix(".difftar")?) {
+        Some(&s[..digits])
+    } else {
+        None
+    }
+}
+
+/// Matches `(\.part)?($|\.)`, returning whether the part is present.
+fn partial_suffix(s: &str) -> Option<bool> {
+    match s.strip_prefix(".part") {
+        Some(rest) if is_end(rest) => Some(true),
+        _ if is_end(s) => Some(false),
+        _ => None,
+    }
+}
+
+fn is_end(s: &str) -> bool {
+    s.is_empty() || s.starts_with('.')
+}
+
+/// Builds the file information for the given file type.
+///
+/// Signatures can also be marked as partial by a `.part` suffix after the compression and
+/// encryption extensions, e.g. `.sigtar.gz.part`.
+fn make_info(tp: Type, lower_fname: &str) -> Info {
+    let (tp, name) = match (tp, lower_fname.strip_suffix(".part")) {
+        (Type::FullSig { time, .. }, Some(name)) => (
+            Type::FullSig {
+                time,
+                partial: true,
+            },
+            name,
+        ),
+        (
+            Type::NewSig {
+                start_time,
+                end_time,
+                ..
+            },
+            Some(name),
+        ) => (
+            Type::NewSig {
+                start_time,
+                end_time,
+                partial: true,
+            },
+            name,
+        ),
+        (tp, _) => (tp, lower_fname),
+    };
+    Info {
+        tp,
+        compression: Compression::from_file_name(name),
+        encrypted: is_encrypted(name),
+    }
+}
+
 fn get_vol_num(s: &str) -> Option<usize> {
     s.parse::<usize>().ok()
 }
 
-fn is_encrypted(s: &str) -> bool {
+pub(crate) fn is_encrypted(s: &str) -> bool {
     s.ends_with(".gpg") || s.ends_with(".g")
 }
 
-fn is_compressed(s: &str) -> bool {
-    s.ends_with(".gz") || s.ends_with(".z")
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -212,7 +544,7 @@ mod test {
                     time: parse_time_str("20150617t182545z").unwrap(),
                     volume_number: 1,
                 },
-                compressed: true,
+                compression: Compression::Gzip,
                 encrypted: false,
             })
         );
@@ -224,7 +556,7 @@ mod test {
                     time: parse_time_str("20150617t182545z").unwrap(),
                     partial: false,
                 },
-                compressed: false,
+                compression: Compression::None,
                 encrypted: false,
             })
         );
@@ -237,7 +569,7 @@ mod test {
                     end_time: parse_time_str("20150617t182650z").unwrap(),
                     volume_number: 1
                 },
-                compressed: true,
+                compression: Compression::Gzip,
                 encrypted: false
             })
         );
@@ -250,7 +582,7 @@ mod test {
                     end_time: parse_time_str("20150617t182629z").unwrap(),
                     partial: false,
                 },
-                compressed: false,
+                compression: Compression::None,
                 encrypted: false,
             })
         );
@@ -263,7 +595,7 @@ mod test {
                     end_time: parse_time_str("20150617t182629z").unwrap(),
                     partial: false
                 },
-                compressed: true,
+                compression: Compression::Gzip,
                 encrypted: false
             })
         );
@@ -275,9 +607,191 @@ mod test {
                     time: parse_time_str("20150617t182545z").unwrap(),
                     partial: false,
                 },
-                compressed: true,
+                compression: Compression::Gzip,
                 encrypted: false,
             })
         );
     }
+
+    #[test]
+    fn parse_with_base_path() {
+        let parser = FileNameParser::new();
+        let plain = PlainFileNameParser::new();
+        let name = "duplicity-full.20150617T182545Z.vol1.difftar.gz";
+        let expected = parser.parse(name).unwrap();
+        let valid = [
+            ("tests/backups/single_vol", "tests/backups/single_vol"),
+            ("tests/backups/single_vol", "tests/backups/single_vol/"),
+            ("tests/backups/single_vol/", "tests/backups/single_vol"),
+            ("/backups/single_vol", "/backups/single_vol"),
+            ("/backups/single_vol", "/backups/single_vol/"),
+            ("/backups", "/backups"),
+            ("dir", "dir"),
+            ("", ""),
+        ];
+        for &(dir, base) in &valid {
+            let full_path = Path::new(dir).join(name);
+            let expected = Some((expected.clone(), Path::new(name)));
+            assert_eq!(
+                parser.parse_with_base_path(&full_path, Path::new(base)),
+                expected,
+                "{:?} in {:?}",
+                full_path,
+                base
+            );
+            assert_eq!(
+                plain.parse_with_base_path(&full_path, Path::new(base)),
+                expected
+            );
+        }
+
+        let invalid = [
+            // not inside the base path
+            ("tests/backups/single_vol", "tests/backups/multi_chain"),
+            ("tests/backups/single_vol", "tests/backups/single"),
+            ("/backups/single_vol", "backups/single_vol"),
+            ("backups/single_vol", "/backups/single_vol"),
+            // the relative path contains a directory
+            ("tests/backups/single_vol", "tests/backups"),
+            ("/backups/single_vol", "/"),
+        ];
+        for &(dir, base) in &invalid {
+            let full_path = Path::new(dir).join(name);
+            assert_eq!(
+                parser.parse_with_base_path(&full_path, Path::new(base)),
+                None,
+                "{:?} in {:?}",
+                full_path,
+                base
+            );
+            assert_eq!(
+                plain.parse_with_base_path(&full_path, Path::new(base)),
+                None
+            );
+        }
+        // not a backup file
+        let full_path = Path::new("tests/backups/single_vol/info");
+        let base = Path::new("tests/backups/single_vol");
+        assert_eq!(parser.parse_with_base_path(full_path, base), None);
+        assert_eq!(plain.parse_with_base_path(full_path, base), None);
+    }
+
+    #[test]
+    fn partial_signatures() {
+        let parser = FileNameParser::new();
+        let time = parse_time_str("20150617t182545z").unwrap();
+        let full_sig = |partial, compression| {
+            Some(Info {
+                tp: Type::FullSig { time, partial },
+                compression,
+                encrypted: false,
+            })
+        };
+        assert_eq!(
+            parser.parse("duplicity-full-signatures.20150617T182545Z.sigtar.part.gz"),
+            full_sig(true, Compression::Gzip)
+        );
+        assert_eq!(
+            parser.parse("duplicity-full-signatures.20150617T182545Z.sigtar.gz.part"),
+            full_sig(true, Compression::Gzip)
+        );
+        assert_eq!(
+            parser.parse("duplicity-full-signatures.20150617T182545Z.sigtar.part"),
+            full_sig(true, Compression::None)
+        );
+        assert_eq!(
+            parser.parse("duplicity-full-signatures.20150617T182545Z.sigtar.gz"),
+            full_sig(false, Compression::Gzip)
+        );
+        let new_sig = parser
+            .parse("duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.gz.part")
+            .unwrap();
+        assert!(matches!(new_sig.tp, Type::NewSig { partial: true, .. }));
+        assert_eq!(new_sig.compression, Compression::Gzip);
+    }
+
+    #[test]
+    fn display() {
+        let parser = FileNameParser::new();
+        let display = |name: &str| parser.parse(name).unwrap().to_string();
+        assert_eq!(
+            display("duplicity-full.20150617T182545Z.vol1.difftar.gz"),
+            "Full backup volume 1 at 2015-06-17T18:25:45Z (gzip)"
+        );
+        assert_eq!(
+            display("duplicity-full.20150617T182545Z.manifest"),
+            "Full backup manifest at 2015-06-17T18:25:45Z"
+        );
+        assert_eq!(
+            display("duplicity-inc.20150617T182629Z.to.20150617T182650Z.vol2.difftar.gpg"),
+            "Incremental backup volume 2 from 2015-06-17T18:26:29Z to 2015-06-17T18:26:50Z \
+             (encrypted)"
+        );
+        assert_eq!(
+            display("duplicity-inc.20150617T182545Z.to.20150617T182629Z.manifest.part"),
+            "Incremental backup manifest from 2015-06-17T18:25:45Z to 2015-06-17T18:26:29Z \
+             (partial)"
+        );
+        assert_eq!(
+            display("duplicity-full-signatures.20150617T182545Z.sigtar.part.gz"),
+            "Full signatures at 2015-06-17T18:25:45Z (partial) (gzip)"
+        );
+        assert_eq!(
+            display("duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.gz"),
+            "Incremental signatures from 2015-06-17T18:25:45Z to 2015-06-17T18:26:29Z \
+             (gzip)"
+        );
+        assert_eq!(
+            display("duplicity-full-signatures.20150617T182545Z.sigtar.bz2"),
+            "Full signatures at 2015-06-17T18:25:45Z (bzip2)"
+        );
+        assert_eq!(
+            display("duplicity-full.20150617T182545Z.vol1.difftar.zst"),
+            "Full backup volume 1 at 2015-06-17T18:25:45Z (zstd)"
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn plain_parser_same_results() {
+        use std::fs;
+
+        let mut names = vec![
+            "invalid".to_owned(),
+            "duplicity-full.20150617T182545Z.vol12.difftar.gz".to_owned(),
+            "duplicity-full.20150617T182545Z.vol1.difftar.part".to_owned(),
+            "duplicity-full.20150617T182545Z.vol.difftar.gz".to_owned(),
+            "duplicity-full.20150617T182545Z.vol1.difftarx".to_owned(),
+            "duplicity-full.20150617T182545Z.manifest.part".to_owned(),
+            "duplicity-full.20150617T182545Z.manifest.partx".to_owned(),
+            "duplicity-full.20150617T182545Z.manifestx".to_owned(),
+            "duplicity-full.invalid.manifest".to_owned(),
+            "duplicity-inc.20150617T182545Z.to.20150617T182629Z.vol3.difftar.gpg".to_owned(),
+            "duplicity-inc.20150617T182545Z.to.20150617T182629Z.manifest.part.gpg".to_owned(),
+            "duplicity-inc.20150617T182545Z.20150617T182629Z.manifest".to_owned(),
+            "duplicity-full-signatures.20150617T182545Z.sigtar.part".to_owned(),
+            "duplicity-full-signatures.20150617T182545Z.sigtar".to_owned(),
+            "duplicity-full-signatures.20150617T182545Z.sigtar.gz.part".to_owned(),
+            "duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.gpg.part"
+                .to_owned(),
+            "duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.part.gz"
+                .to_owned(),
+            "DUPLICITY-FULL.20150617T182545Z.VOL1.DIFFTAR.GZ".to_owned(),
+        ];
+        for dir in fs::read_dir("tests/backups").unwrap() {
+            let dir = dir.unwrap().path();
+            if dir.is_dir() {
+                for file in fs::read_dir(dir).unwrap() {
+                    let file = file.unwrap().file_name();
+                    names.push(file.to_str().unwrap().to_owned());
+                }
+            }
+        }
+
+        let parser = FileNameParser::new();
+        let plain = PlainFileNameParser::new();
+        for name in &names {
+            assert_eq!(plain.parse(name), parser.parse(name), "{}", name);
+        }
+    }
 }
@@ -5,13 +5,17 @@
 
 mod file_naming;
 
+pub(crate) use self::file_naming::is_encrypted;
+pub use self::file_naming::{FileNameParser, Info, PlainFileNameParser, Type};
+pub use crate::compression::Compression;
+
+use std::cmp::Ordering;
 use std::fmt::{Display, Error, Formatter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::slice;
 use time::Timespec;
 
-use self::file_naming as fnm;
-use self::file_naming::{FileNameInfo, FileNameParser};
+use self::file_naming::FileNameInfo;
 use crate::timefmt::TimeDisplay;
 
 /// General information about a backup.
@@ -19,17 +23,23 @@ use crate::timefmt::TimeDisplay;
 /// Determines the status of a backup by looking at the files present in the backup folder. No
 /// backup archive is opened in this process. Thanks to that, performances are great; however no
 /// validation is performed on backup files.
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct Collections {
     backup_chains: Vec<BackupChain>,
     sig_chains: Vec<SignatureChain>,
+    ignored_files: Vec<PathBuf>,
 }
 
+/// Allows to display a collection, with the signature chain paired with each backup chain.
+///
+/// See [`Collections::display_full`](struct.Collections.html#method.display_full).
+pub struct CollectionsDisplay<'a>(&'a Collections);
+
 /// Contains information about a backup chain.
 ///
 /// A backup chain is composed by one full and all the incremental backup snapshots before the
 /// next full one.
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct BackupChain {
     fullset: BackupSet,
     incsets: Vec<BackupSet>,
@@ -40,7 +50,7 @@ pub struct BackupChain {
 /// Contains information about signatures in a backup chain.
 ///
 /// See the docs for [`BackupChain`](struct.BackupChain.html).
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct SignatureChain {
     fullsig: SignatureFile,
     incsigs: Vec<SignatureFile>,
@@ -49,9 +59,9 @@ pub struct SignatureChain {
 /// Information about the files which make a backup snapshot.
 ///
 /// This struct contains paths for the manifest and volumes, and the backup format.
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct BackupSet {
-    tp: Type,
+    tp: SetType,
     compressed: bool,
     encrypted: bool,
     partial: bool,
@@ -60,16 +70,65 @@ pub struct BackupSet {
 }
 
 /// Information about a signature file.
-#[derive(Debug)]
+///
+/// Signature files are ordered by time. Files with the same time are ordered by their other
+/// fields, to be consistent with equality.
+#[derive(Debug, Eq, PartialEq)]
 pub struct SignatureFile {
     /// The file name of the signature file.
     pub file_name: String,
     /// The snapshot time.
     pub time: Timespec,
-    /// Determine if the signature is gzipped or not.
+    /// Determine if the signature is compressed or not.
     pub compressed: bool,
     /// Determine if the signature is encrypted or not.
     pub encrypted: bool,
+    /// Determine if the signature is partial, i.e. its upload has not been completed.
+    pub partial: bool,
+}
+
+/// An inconsistency between a signature chain and its backup chain.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError {
+    /// The chains start at different times.
+    StartTimeMismatch {
+        /// The start time of the signature chain.
+        sig: Timespec,
+        /// The start time of the backup chain.
+        backup: Timespec,
+    },
+    /// The chains end at different times.
+    EndTimeMismatch {
+        /// The end time of the signature chain.
+        sig: Timespec,
+        /// The end time of the backup chain.
+        backup: Timespec,
+    },
+    /// The chains have a different number of incremental snapshots.
+    CountMismatch {
+        /// The number of incremental signatures.
+        sig_count: usize,
+        /// The number of incremental backup sets.
+        backup_count: usize,
+    },
+}
+
+/// An inconsistency between the backup chains and the signature chains of a collection.
+///
+/// Chains are identified by their index, in chronological order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ValidationIssue {
+    /// The backup chain has no corresponding signature chain.
+    MissingSignatureChain(usize),
+    /// The signature chain has no corresponding backup chain.
+    MissingBackupChain(usize),
+    /// The signature chain does not match the corresponding backup chain.
+    ChainMismatch {
+        /// The index of the chains.
+        chain: usize,
+        /// The inconsistency found.
+        error: ValidationError,
+    },
 }
 
 /// Iterator over some kind of chain.
@@ -82,7 +141,7 @@ pub type BackupSetIter<'a> = slice::Iter<'a, BackupSet>;
 pub type SignatureFileIter<'a> = slice::Iter<'a, SignatureFile>;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum Type {
+enum SetType {
     Full {
         time: Timespec,
     },
@@ -102,7 +161,9 @@ impl BackupSet {
 
     /// Returns whether the set is partial.
     ///
-    /// This could happen when a backup is stopped before the completion.
+    /// This could happen when a backup is stopped before the completion. If both a partial and
+    /// a complete manifest are present for the same set, the complete one is used and the set is
+    /// not partial.
     pub fn is_partial(&self) -> bool {
         self.partial
     }
@@ -154,38 +215,56 @@ impl BackupSet {
 
     /// Returns whether the set is a full backup.
     pub fn is_full(&self) -> bool {
-        matches!(self.tp, Type::Full{..})
+        matches!(self.tp, SetType::Full { .. })
     }
 
     /// Returns whether the set is a full backup.
     pub fn is_incremental(&self) -> bool {
-        matches!(self.tp, Type::Inc{..})
+        matches!(self.tp, SetType::Inc { .. })
+    }
+
+    /// Sets the path of the given volume, replacing the previous one if present.
+    ///
+    /// Volumes are numbered starting from one.
+    pub fn add_volume(&mut self, vol_num: usize, path: String) {
+        // resize volumes if necessary
+        if vol_num >= self.volumes_paths.len() {
+            self.volumes_paths.resize(vol_num + 1, None);
+        }
+        self.volumes_paths[vol_num] = Some(path);
+    }
+
+    /// Sets the path of the manifest file for the set.
+    ///
+    /// This makes the set complete.
+    pub fn set_manifest_path(&mut self, path: String) {
+        self.manifest_path = path;
     }
 
     /// Returns whether the given file belongs to the same backup set, by looking at timestamps.
-    pub fn is_same_set(&self, pr: &fnm::Info) -> bool {
+    pub fn is_same_set(&self, pr: &Info) -> bool {
         match self.tp {
-            Type::Full { time: my_time } => match pr.tp {
-                fnm::Type::Full { time, .. }
-                | fnm::Type::FullManifest { time, .. }
-                | fnm::Type::FullSig { time, .. } => my_time == time,
+            SetType::Full { time: my_time } => match pr.tp {
+                Type::Full { time, .. }
+                | Type::FullManifest { time, .. }
+                | Type::FullSig { time, .. } => my_time == time,
                 _ => false,
             },
-            Type::Inc {
+            SetType::Inc {
                 start_time: my_start,
                 end_time: my_end,
             } => match pr.tp {
-                fnm::Type::Inc {
+                Type::Inc {
                     start_time,
                     end_time,
                     ..
                 }
-                | fnm::Type::IncManifest {
+                | Type::IncManifest {
                     start_time,
                     end_time,
                     ..
                 }
-                | fnm::Type::NewSig {
+                | Type::NewSig {
                     start_time,
                     end_time,
                     ..
@@ -199,24 +278,24 @@ impl BackupSet {
     fn new(fname: &FileNameInfo) -> Self {
         // set type
         let tp = match fname.info.tp {
-            fnm::Type::Full { time, .. }
-            | fnm::Type::FullManifest { time, .. }
-            | fnm::Type::FullSig { time, .. } => Type::Full { time: time },
-            fnm::Type::Inc {
+            Type::Full { time, .. }
+            | Type::FullManifest { time, .. }
+            | Type::FullSig { time, .. } => SetType::Full { time: time },
+            Type::Inc {
                 start_time,
                 end_time,
                 ..
             }
-            | fnm::Type::IncManifest {
+            | Type::IncManifest {
                 start_time,
                 end_time,
                 ..
             }
-            | fnm::Type::NewSig {
+            | Type::NewSig {
                 start_time,
                 end_time,
                 ..
-            } => Type::Inc {
+            } => SetType::Inc {
                 start_time: start_time,
                 end_time: end_time,
             },
@@ -224,18 +303,18 @@ impl BackupSet {
         // set partial
         let partial = {
             match fname.info.tp {
-                fnm::Type::FullManifest { partial, .. }
-                | fnm::Type::IncManifest { partial, .. }
-                | fnm::Type::FullSig { partial, .. }
-                | fnm::Type::NewSig { partial, .. } => partial,
+                Type::FullManifest { partial, .. }
+                | Type::IncManifest { partial, .. }
+                | Type::FullSig { partial, .. }
+                | Type::NewSig { partial, .. } => partial,
                 _ => false,
             }
         };
 
         let mut result = BackupSet {
             tp: tp,
-            partial: partial,
-            compressed: fname.info.compressed,
+            partial,
+            compressed: fname.info.compression.is_compressed(),
             encrypted: fname.info.encrypted,
             manifest_path: String::new(),
             volumes_paths: Vec::new(),
@@ -258,18 +337,15 @@ impl BackupSet {
         } else {
             // update info
             match pr.tp {
-                fnm::Type::Full { volume_number, .. } | fnm::Type::Inc { volume_number, .. } => {
-                    // resize volumes if necessary
-                    if volume_number >= self.volumes_paths.len() {
-                        self.volumes_paths.reserve(volume_number + 1);
-                        for _ in self.volumes_paths.len()..volume_number + 1 {
-                            self.volumes_paths.push(None);
-                        }
-                    }
-                    self.volumes_paths[volume_number] = Some(fname.to_owned());
+                Type::Full { volume_number, .. } | Type::Inc { volume_number, .. } => {
+                    self.add_volume(volume_number, fname.to_owned());
                 }
-                fnm::Type::FullManifest { .. } | fnm::Type::IncManifest { .. } => {
-                    self.manifest_path = fname.to_owned();
+                // a complete manifest is preferred over a partial one with the same time
+                Type::FullManifest { partial, .. } | Type::IncManifest { partial, .. }
+                    if !partial || self.partial || self.manifest_path.is_empty() =>
+                {
+                    self.set_manifest_path(fname.to_owned());
+                    self.partial = partial;
                 }
                 _ => (),
             }
@@ -285,11 +361,31 @@ impl BackupSet {
     }
 }
 
+impl PartialOrd for BackupSet {
+    fn partial_cmp(&self, other: &BackupSet) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Backup sets are ordered by end time. Sets with the same end time are ordered by their other
+/// fields, to be consistent with equality.
+impl Ord for BackupSet {
+    fn cmp(&self, other: &BackupSet) -> Ordering {
+        let times = |s: &BackupSet| (s.end_time(), s.start_time(), s.is_incremental());
+        let flags = |s: &BackupSet| (s.compressed, s.encrypted, s.partial);
+        times(self)
+            .cmp(&times(other))
+            .then_with(|| self.manifest_path.cmp(&other.manifest_path))
+            .then_with(|| self.volumes_paths.cmp(&other.volumes_paths))
+            .then_with(|| flags(self).cmp(&flags(other)))
+    }
+}
+
 impl Display for BackupSet {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         let tp = match self.tp {
-            Type::Full { .. } => "Full",
-            Type::Inc { .. } => "Incremental",
+            SetType::Full { .. } => "Full",
+            SetType::Inc { .. } => "Incremental",
         };
         write!(
             f,
@@ -308,7 +404,7 @@ impl BackupChain {
     /// Create a new BackupChain starting from a full backup set.
     pub fn new(fullset: BackupSet) -> Self {
         let time = {
-            if let Type::Full { time } = fullset.tp {
+            if let SetType::Full { time } = fullset.tp {
                 time
             } else {
                 panic!("Unexpected incremental backup set given");
@@ -326,7 +422,7 @@ impl BackupChain {
     /// Adds the given incremental backup element to the backup chain if possible,
     /// returns it back otherwise.
     pub fn add_inc(&mut self, incset: BackupSet) -> Option<BackupSet> {
-        if let Type::Inc {
+        if let SetType::Inc {
             start_time,
             end_time,
         } = incset.tp
@@ -368,6 +464,22 @@ impl BackupChain {
         self.incsets.iter()
     }
 
+    /// Returns the set with the given index in the chain, if present.
+    ///
+    /// The index zero is the full set, and the following are the incremental sets, in
+    /// chronological order.
+    pub fn nth_set(&self, n: usize) -> Option<&BackupSet> {
+        match n {
+            0 => Some(&self.fullset),
+            n => self.incsets.get(n - 1),
+        }
+    }
+
+    /// Returns the number of sets in the chain, including the full one.
+    pub fn num_sets(&self) -> usize {
+        self.incsets.len() + 1
+    }
+
     /// Returns the time of the first backup set in the chain.
     pub fn start_time(&self) -> Timespec {
         self.start_time
@@ -377,6 +489,56 @@ impl BackupChain {
     pub fn end_time(&self) -> Timespec {
         self.end_time
     }
+
+    /// Returns whether the given time is between the start and the end time of the chain,
+    /// inclusive.
+    pub fn contains_snapshot_at(&self, time: Timespec) -> bool {
+        time >= self.start_time && time <= self.end_time
+    }
+
+    /// Returns the backup set with the end time closest to the given time.
+    ///
+    /// If the time is exactly halfway between two sets, the older one is returned.
+    pub fn snapshot_nearest_to(&self, time: Timespec) -> &BackupSet {
+        // incremental sets are sorted by end time, and they all come after the full set
+        let pos = match self
+            .incsets
+            .binary_search_by(|set| set.end_time().cmp(&time))
+        {
+            Ok(pos) => return &self.incsets[pos],
+            Err(pos) => pos,
+        };
+        let before = match pos {
+            0 => &self.fullset,
+            _ => &self.incsets[pos - 1],
+        };
+        match self.incsets.get(pos) {
+            Some(after) if after.end_time() - time < time - before.end_time() => after,
+            _ => before,
+        }
+    }
+
+    /// Returns the backup set representing the state of the backup at the given time.
+    ///
+    /// This is the latest set in the chain with an end time not after the given time, or `None`
+    /// if the time comes before the start of the chain.
+    pub fn set_at_time(&self, time: Timespec) -> Option<&BackupSet> {
+        if time < self.start_time {
+            return None;
+        }
+        // incremental sets are sorted by end time, and they all come after the full set
+        let pos = match self
+            .incsets
+            .binary_search_by(|set| set.end_time().cmp(&time))
+        {
+            Ok(pos) => return Some(&self.incsets[pos]),
+            Err(pos) => pos,
+        };
+        match pos {
+            0 => Some(&self.fullset),
+            _ => Some(&self.incsets[pos - 1]),
+        }
+    }
 }
 
 impl Display for BackupChain {
@@ -413,19 +575,22 @@ impl Display for BackupChain {
 
 impl SignatureFile {
     /// Construct a signature file from a file name and infos.
-    pub fn from_file_and_info(fname: &str, pr: &fnm::Info) -> Self {
-        let time = {
+    pub fn from_file_and_info(fname: &str, pr: &Info) -> Self {
+        let (time, partial) = {
             match pr.tp {
-                fnm::Type::FullSig { time, .. } => time,
-                fnm::Type::NewSig { end_time, .. } => end_time,
+                Type::FullSig { time, partial } => (time, partial),
+                Type::NewSig {
+                    end_time, partial, ..
+                } => (end_time, partial),
                 _ => panic!("unexpected file given for signature"),
             }
         };
         SignatureFile {
             file_name: fname.to_owned(),
             time: time,
-            compressed: pr.compressed,
+            compressed: pr.compression.is_compressed(),
             encrypted: pr.encrypted,
+            partial,
         }
     }
 
@@ -433,11 +598,48 @@ impl SignatureFile {
     pub fn from_filename_info(info: &FileNameInfo) -> Self {
         Self::from_file_and_info(info.file_name, &info.info)
     }
+
+    /// Returns whether this signature is more recent than the other one.
+    pub fn is_newer_than(&self, other: &SignatureFile) -> bool {
+        self.time > other.time
+    }
+
+    /// Returns whether this signature is older than the other one.
+    pub fn is_older_than(&self, other: &SignatureFile) -> bool {
+        self.time < other.time
+    }
+
+    /// Returns whether this signature has the same time of the other one.
+    pub fn is_same_time(&self, other: &SignatureFile) -> bool {
+        self.time == other.time
+    }
+
+    fn sort_key(&self) -> (Timespec, &str, bool, bool, bool) {
+        (
+            self.time,
+            &self.file_name,
+            self.compressed,
+            self.encrypted,
+            self.partial,
+        )
+    }
+}
+
+impl PartialOrd for SignatureFile {
+    fn partial_cmp(&self, other: &SignatureFile) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SignatureFile {
+    fn cmp(&self, other: &SignatureFile) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
 }
 
 impl SignatureChain {
     /// Create a new SignatureChain starting from a full signature.
-    pub fn new(fname: &str, pr: &fnm::Info) -> Self {
+    pub fn new(fname: &str, pr: &Info) -> Self {
         SignatureChain {
             fullsig: SignatureFile::from_file_and_info(fname, pr),
             incsigs: Vec::new(),
@@ -452,7 +654,7 @@ impl SignatureChain {
     /// Adds the given incremental signature to the signature chain if possible,
     /// returns false otherwise.
     pub fn add_new_sig(&mut self, fname: &FileNameInfo) -> bool {
-        if matches!(fname.info.tp, fnm::Type::NewSig{ .. }) {
+        if matches!(fname.info.tp, Type::NewSig { .. }) {
             self.incsigs.push(SignatureFile::from_filename_info(fname));
             true
         } else {
@@ -481,6 +683,32 @@ impl SignatureChain {
             .last()
             .map_or(self.start_time(), |inc| inc.time)
     }
+
+    /// Checks that this signature chain covers the same snapshots of the given backup chain.
+    ///
+    /// Returns all the inconsistencies found, so an empty list means that the chains match.
+    pub fn validate_against_backup_chain(&self, bchain: &BackupChain) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.start_time() != bchain.start_time() {
+            errors.push(ValidationError::StartTimeMismatch {
+                sig: self.start_time(),
+                backup: bchain.start_time(),
+            });
+        }
+        if self.end_time() != bchain.end_time() {
+            errors.push(ValidationError::EndTimeMismatch {
+                sig: self.end_time(),
+                backup: bchain.end_time(),
+            });
+        }
+        if self.incsigs.len() != bchain.incsets.len() {
+            errors.push(ValidationError::CountMismatch {
+                sig_count: self.incsigs.len(),
+                backup_count: bchain.incsets.len(),
+            });
+        }
+        errors
+    }
 }
 
 impl Display for SignatureChain {
@@ -499,19 +727,27 @@ impl Display for SignatureChain {
     }
 }
 
+impl Default for Collections {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Collections {
     /// Creates a new empty collection.
     pub fn new() -> Self {
         Collections {
             backup_chains: Vec::new(),
             sig_chains: Vec::new(),
+            ignored_files: Vec::new(),
         }
     }
 
     /// Creates a collection, starting from a list of file names.
     ///
     /// The given file names are not opened for validation. Information is collected based solely
-    /// by the names themselves.
+    /// by the names themselves. The names can contain a directory prefix, that is preserved in
+    /// the paths of the backup and signature files.
     ///
     /// # Examples
     /// ```
@@ -532,11 +768,90 @@ impl Collections {
         I: IntoIterator,
         I::Item: AsRef<Path>,
     {
-        let fnames_vec: Vec<_> = filenames.into_iter().collect();
-        let infos = compute_filename_infos(&fnames_vec);
+        Self::from_filename_iter(filenames)
+    }
+
+    /// Creates a collection, by processing the given file names one at a time.
+    ///
+    /// The names are not collected upfront: only the information about backup and signature
+    /// files is retained while iterating. This allows to stream the names directly from a
+    /// backend listing, without materializing it, even for directories containing a huge
+    /// number of files. The result is the same as `from_filenames`.
+    ///
+    /// # Examples
+    /// ```
+    /// use ruplicity::backend::Backend;
+    /// use ruplicity::backend::local::LocalBackend;
+    /// use ruplicity::collections::Collections;
+    ///
+    /// let backend = LocalBackend::new("tests/backups/single_vol");
+    /// let collections = Collections::from_filename_iter(backend.file_names().unwrap());
+    /// assert_eq!(collections.backup_chains().count(), 1);
+    /// ```
+    pub fn from_filename_iter<I>(filenames: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        Self::from_filenames_with(&FileNameParser::new(), filenames)
+    }
+
+    /// Creates a collection from the given file names, by using an existing parser.
+    ///
+    /// Creating a `FileNameParser` compiles its regular expressions, so reusing the same parser
+    /// is faster when building many collections, e.g. one for each directory in a tree. The
+    /// result is the same as `from_filenames`.
+    ///
+    /// # Examples
+    /// ```
+    /// use ruplicity::backend::Backend;
+    /// use ruplicity::backend::local::LocalBackend;
+    /// use ruplicity::collections::{Collections, FileNameParser};
+    ///
+    /// let parser = FileNameParser::new();
+    /// for dir in &["tests/backups/single_vol", "tests/backups/multi_chain"] {
+    ///     let backend = LocalBackend::new(dir);
+    ///     let collections = Collections::from_filenames_with(&parser, backend.file_names().unwrap());
+    ///     assert!(collections.num_chains() > 0);
+    /// }
+    /// ```
+    pub fn from_filenames_with<I>(parser: &FileNameParser, filenames: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let mut backup_sets = Vec::new();
+        // only signature files need to be kept until all the names are known
+        let mut sig_files = Vec::new();
+        let mut ignored_files = Vec::new();
+        for path in filenames {
+            let path = path.as_ref();
+            let name = match path.to_str() {
+                Some(name) => name,
+                None => {
+                    // backup files cannot be represented without a valid UTF-8 path
+                    ignored_files.push(path.to_owned());
+                    continue;
+                }
+            };
+            if let Some(fname_info) = parse_filename(parser, name) {
+                add_to_backup_sets(&mut backup_sets, &fname_info);
+                match fname_info.info.tp {
+                    Type::FullSig { .. } | Type::NewSig { .. } => {
+                        sig_files.push((fname_info.file_name.to_owned(), fname_info.info))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        let sig_infos = sig_files
+            .iter()
+            .map(|(name, info)| FileNameInfo::new(name, info.clone()))
+            .collect::<Vec<_>>();
         Collections {
-            backup_chains: compute_backup_chains(&infos),
-            sig_chains: compute_signature_chains(&infos),
+            backup_chains: compute_backup_chains(backup_sets),
+            sig_chains: compute_signature_chains(&sig_infos),
+            ignored_files,
         }
     }
 
@@ -556,6 +871,49 @@ impl Collections {
         self.sig_chains.iter()
     }
 
+    /// Returns the number of backup chains.
+    pub fn num_chains(&self) -> usize {
+        self.backup_chains.len()
+    }
+
+    /// Returns the backup chain with the given index, if present.
+    ///
+    /// Chains are indexed starting from zero, in chronological order.
+    pub fn backup_chain(&self, index: usize) -> Option<&BackupChain> {
+        self.backup_chains.get(index)
+    }
+
+    /// Returns the full backup set of the most recent backup chain, if present.
+    pub fn latest_full_backup(&self) -> Option<&BackupSet> {
+        self.backup_chains.last().map(BackupChain::full_set)
+    }
+
+    /// Returns the full backup set of the oldest backup chain, if present.
+    pub fn oldest_full_backup(&self) -> Option<&BackupSet> {
+        self.backup_chains.first().map(BackupChain::full_set)
+    }
+
+    /// Returns the number of signature chains.
+    pub fn num_signature_chains(&self) -> usize {
+        self.sig_chains.len()
+    }
+
+    /// Returns the signature chain with the given index, if present.
+    ///
+    /// Chains are indexed starting from zero, in chronological order.
+    pub fn signature_chain(&self, index: usize) -> Option<&SignatureChain> {
+        self.sig_chains.get(index)
+    }
+
+    /// Returns the file names that have been ignored because they are not valid UTF-8.
+    ///
+    /// Duplicity file names are always valid UTF-8, but a directory prefix or a mis-encoded
+    /// file system entry could not be. Other names, not recognized as backup files, are simply
+    /// skipped and not reported here.
+    pub fn ignored_files(&self) -> &[PathBuf] {
+        &self.ignored_files
+    }
+
     /// Returns the total number of snapshots.
     pub fn num_snapshots(&self) -> usize {
         let mut i = 0;
@@ -564,33 +922,96 @@ impl Collections {
         }
         i
     }
+
+    /// Returns all the backup sets of all the chains, ordered by end time.
+    ///
+    /// Each set is paired with its index in this order. Chains usually don't overlap in time, so
+    /// the order is the same as iterating the chains one after the other; otherwise the sets of
+    /// the different chains are interleaved. Sets with the same end time are kept in the order
+    /// of their chains.
+    pub fn iter_all_backup_sets(&self) -> impl Iterator<Item = (usize, &BackupSet)> {
+        let mut sets = Vec::with_capacity(self.num_snapshots());
+        for chain in &self.backup_chains {
+            sets.push(chain.full_set());
+            sets.extend(chain.inc_sets());
+        }
+        // the sort is stable, and merges the already sorted chains
+        sets.sort_by_key(|s| s.end_time());
+        sets.into_iter().enumerate()
+    }
+
+    /// Returns all the signature files of all the chains, ordered by time.
+    ///
+    /// Each file is paired with its index in this order. See `iter_all_backup_sets` for details.
+    pub fn iter_all_signature_files(&self) -> impl Iterator<Item = (usize, &SignatureFile)> {
+        let mut sigs = Vec::new();
+        for chain in &self.sig_chains {
+            sigs.push(chain.full_signature());
+            sigs.extend(chain.inc_signatures());
+        }
+        sigs.sort_by_key(|s| s.time);
+        sigs.into_iter().enumerate()
+    }
+
+    /// Returns an object displaying both backup and signature chains.
+    ///
+    /// Each backup chain is followed by the signature chain paired with it, similarly to the
+    /// output of `duplicity collection-status`. The `Display` of `Collections` only shows backup
+    /// chains instead.
+    pub fn display_full(&self) -> CollectionsDisplay<'_> {
+        CollectionsDisplay(self)
+    }
+
+    /// Checks that each signature chain matches the backup chain paired with it.
+    ///
+    /// Chains are paired by their chronological order. Returns all the inconsistencies found, so
+    /// an empty list means that signatures and backup sets match.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for (i, (sig, backup)) in self.sig_chains.iter().zip(&self.backup_chains).enumerate() {
+            issues.extend(
+                sig.validate_against_backup_chain(backup)
+                    .into_iter()
+                    .map(|error| ValidationIssue::ChainMismatch { chain: i, error }),
+            );
+        }
+        let paired = self.sig_chains.len().min(self.backup_chains.len());
+        for i in paired..self.backup_chains.len() {
+            issues.push(ValidationIssue::MissingSignatureChain(i));
+        }
+        for i in paired..self.sig_chains.len() {
+            issues.push(ValidationIssue::MissingBackupChain(i));
+        }
+        issues
+    }
 }
 
-fn compute_filename_infos<'a, I, E>(filenames: I) -> Vec<FileNameInfo<'a>>
-where
-    I: IntoIterator<Item = &'a E>,
-    E: AsRef<Path> + 'a,
-{
-    let parser = FileNameParser::new();
-    filenames
-        .into_iter()
-        .filter_map(|path| path.as_ref().to_str())
-        .filter_map(|name| match parser.parse(name) {
-            Some(info) => Some(FileNameInfo::new(name, info)),
-            None => None,
-        })
-        .collect()
+fn parse_filename<'a>(parser: &FileNameParser, path: &'a str) -> Option<FileNameInfo<'a>> {
+    // parse only the file name, but keep the whole path, so the file can be opened
+    let info = parser.parse(Path::new(path).file_name()?.to_str()?)?;
+    Some(FileNameInfo::new(path, info))
 }
 
-fn compute_backup_chains(fname_infos: &[FileNameInfo]) -> Vec<BackupChain> {
+fn add_to_backup_sets(sets: &mut Vec<BackupSet>, fileinfo: &FileNameInfo) {
+    for set in sets.iter_mut() {
+        if set.add_filename(fileinfo) {
+            return;
+        }
+    }
+    sets.push(BackupSet::new(fileinfo));
+}
+
+fn compute_backup_chains(mut backup_sets: Vec<BackupSet>) -> Vec<BackupChain> {
+    // sort sets by time
+    backup_sets.sort_by(|a, b| a.end_time().cmp(&b.end_time()));
     let mut backup_chains: Vec<BackupChain> = Vec::new();
-    for set in compute_backup_sets(fname_infos) {
+    for set in backup_sets {
         match set.tp {
-            Type::Full { .. } => {
+            SetType::Full { .. } => {
                 let new_chain = BackupChain::new(set);
                 backup_chains.push(new_chain);
             }
-            Type::Inc { .. } => {
+            SetType::Inc { .. } => {
                 let mut rejected_set = Some(set);
                 for chain in &mut backup_chains {
                     rejected_set = chain.add_inc(rejected_set.unwrap());
@@ -609,40 +1030,28 @@ fn compute_backup_chains(fname_infos: &[FileNameInfo]) -> Vec<BackupChain> {
     backup_chains
 }
 
-fn compute_backup_sets(fname_infos: &[FileNameInfo]) -> Vec<BackupSet> {
-    let mut sets = Vec::<BackupSet>::new();
-    for fileinfo in fname_infos {
-        let mut inserted = false;
-        for set in &mut sets {
-            if set.add_filename(&fileinfo) {
-                inserted = true;
-                break;
-            }
-        }
-        if !inserted {
-            sets.push(BackupSet::new(&fileinfo));
-        }
-    }
-    // sort by time
-    sets.sort_by(|a, b| a.end_time().cmp(&b.end_time()));
-    sets
-}
-
 fn compute_signature_chains(fname_infos: &[FileNameInfo]) -> Vec<SignatureChain> {
     // collect full signatures, sort them by start time and make the chains from them
+    // complete signatures come before partial ones with the same time, and are preferred
     let mut sig_chains = fname_infos
         .iter()
-        .filter(|f| matches!(f.info.tp, fnm::Type::FullSig{..}))
+        .filter(|f| matches!(f.info.tp, Type::FullSig { .. }))
         .map(SignatureChain::from_filename_info)
         .collect::<Vec<_>>();
-    sig_chains.sort_by(|a, b| a.start_time().cmp(&b.start_time()));
-    // collect inc signatures and sort them by start time
+    sig_chains.sort_by_key(|c| (c.start_time(), c.fullsig.partial));
+    sig_chains.dedup_by_key(|c| c.start_time());
+    // collect inc signatures and sort them by start time, complete ones first
     let inc_sigs = {
         let mut is = fname_infos
             .iter()
-            .filter(|f| matches!(f.info.tp, fnm::Type::NewSig{..}))
+            .filter(|f| matches!(f.info.tp, Type::NewSig { .. }))
             .collect::<Vec<_>>();
-        is.sort_by(|a, b| a.start_time().cmp(&b.start_time()));
+        is.sort_by_key(|f| {
+            (
+                f.start_time(),
+                matches!(f.info.tp, Type::NewSig { partial: true, .. }),
+            )
+        });
         is
     };
     // add inc signatures to chains
@@ -661,6 +1070,49 @@ fn compute_signature_chains(fname_infos: &[FileNameInfo]) -> Vec<SignatureChain>
     sig_chains
 }
 
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            ValidationError::StartTimeMismatch { sig, backup } => write!(
+                f,
+                "signature chain starts at {}, backup chain at {}",
+                sig.into_local_display(),
+                backup.into_local_display()
+            ),
+            ValidationError::EndTimeMismatch { sig, backup } => write!(
+                f,
+                "signature chain ends at {}, backup chain at {}",
+                sig.into_local_display(),
+                backup.into_local_display()
+            ),
+            ValidationError::CountMismatch {
+                sig_count,
+                backup_count,
+            } => write!(
+                f,
+                "{} incremental signatures for {} incremental backup sets",
+                sig_count, backup_count
+            ),
+        }
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            ValidationIssue::MissingSignatureChain(i) => {
+                write!(f, "backup chain {} has no signature chain", i)
+            }
+            ValidationIssue::MissingBackupChain(i) => {
+                write!(f, "signature chain {} has no backup chain", i)
+            }
+            ValidationIssue::ChainMismatch { chain, error } => {
+                write!(f, "chain {}: {}", chain, error)
+            }
+        }
+    }
+}
+
 impl Display for Collections {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         for backup_chain in &self.backup_chains {
@@ -670,18 +1122,35 @@ impl Display for Collections {
     }
 }
 
-impl Type {
+impl<'a> Display for CollectionsDisplay<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let coll = self.0;
+        for (i, backup_chain) in coll.backup_chains.iter().enumerate() {
+            backup_chain.fmt(f)?;
+            match coll.sig_chains.get(i) {
+                Some(sig_chain) => write!(f, "Signature chain {}", sig_chain)?,
+                None => writeln!(f, "No signature chain")?,
+            }
+        }
+        for sig_chain in coll.sig_chains.iter().skip(coll.backup_chains.len()) {
+            write!(f, "Signature chain without backups {}", sig_chain)?;
+        }
+        Ok(())
+    }
+}
+
+impl SetType {
     pub fn start_time(&self) -> Timespec {
         match *self {
-            Type::Full { time } => time,
-            Type::Inc { start_time, .. } => start_time,
+            SetType::Full { time } => time,
+            SetType::Inc { start_time, .. } => start_time,
         }
     }
 
     pub fn end_time(&self) -> Timespec {
         match *self {
-            Type::Full { time } => time,
-            Type::Inc { end_time, .. } => end_time,
+            SetType::Full { time } => time,
+            SetType::Inc { end_time, .. } => end_time,
         }
     }
 }
@@ -690,6 +1159,8 @@ impl Type {
 mod test {
     use super::file_naming::{FileNameInfo, FileNameParser};
     use super::*;
+    use crate::backend::local::LocalBackend;
+    use crate::backend::Backend;
     use crate::timefmt::parse_time_str;
 
     fn get_test_filenames() -> Vec<&'static str> {
@@ -730,6 +1201,88 @@ mod test {
         assert_eq!(set.manifest_path, manifest1_name);
     }
 
+    #[test]
+    fn signature_file_order() {
+        let collection = Collections::from_filenames(get_test_filenames());
+        let chain = collection.signature_chains().next().unwrap();
+        let full = chain.full_signature();
+        let incs = chain.inc_signatures().collect::<Vec<_>>();
+        assert!(incs[0].is_newer_than(full));
+        assert!(full.is_older_than(incs[0]));
+        assert!(!full.is_newer_than(full) && !full.is_older_than(full));
+        assert!(full.is_same_time(full));
+        assert!(!incs[1].is_same_time(incs[0]));
+
+        let mut sigs = vec![incs[1], full, incs[0]];
+        sigs.sort();
+        assert_eq!(sigs, vec![full, incs[0], incs[1]]);
+
+        // same time, different files
+        let parser = FileNameParser::new();
+        let name = "duplicity-full-signatures.20150617T182545Z.sigtar";
+        let uncompressed = SignatureFile::from_file_and_info(name, &parser.parse(name).unwrap());
+        assert!(uncompressed.is_same_time(full));
+        assert_ne!(&uncompressed, full);
+        assert_eq!(uncompressed.cmp(full), Ordering::Less);
+    }
+
+    #[test]
+    fn backup_set_order() {
+        let collection = Collections::from_filenames(get_test_filenames());
+        let chain = collection.backup_chains().next().unwrap();
+        let full = chain.full_set();
+        let incs = chain.inc_sets().collect::<Vec<_>>();
+        let mut sets = vec![incs[1], incs[0], full];
+        sets.sort();
+        assert_eq!(sets, vec![full, incs[0], incs[1]]);
+        assert_eq!(full.cmp(full), Ordering::Equal);
+    }
+
+    #[test]
+    fn collections_eq() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let first = Collections::from_filenames(backend.file_names().unwrap());
+        let second = Collections::from_filenames(backend.file_names().unwrap());
+        assert_eq!(first, second);
+        assert_eq!(first.backup_chain(0), second.backup_chain(0));
+        assert_eq!(first.signature_chain(1), second.signature_chain(1));
+        assert_ne!(first.backup_chain(0), second.backup_chain(1));
+
+        let mut names = get_test_filenames();
+        let single_vol = Collections::from_filenames(&names);
+        assert_eq!(single_vol, Collections::from_filenames(&names));
+        assert_ne!(single_vol, first);
+        // a missing volume is detected
+        names.retain(|n| !n.ends_with(".vol1.difftar.gz"));
+        assert_ne!(single_vol, Collections::from_filenames(&names));
+    }
+
+    #[test]
+    fn build_set() {
+        let sig_name = "duplicity-full-signatures.20150617T182545Z.sigtar.gz";
+        let parser = FileNameParser::new();
+        let mut set = BackupSet::new(&FileNameInfo::new(
+            sig_name,
+            parser.parse(sig_name).unwrap(),
+        ));
+        assert!(!set.is_complete());
+        assert_eq!(set.volume_path(1), None);
+
+        set.add_volume(2, "vol2".to_owned());
+        assert_eq!(set.volume_path(1), None);
+        assert_eq!(set.volume_path(2), Some("vol2"));
+        set.add_volume(1, "vol1".to_owned());
+        assert_eq!(set.volume_path(1), Some("vol1"));
+        assert_eq!(set.volume_path(2), Some("vol2"));
+        assert_eq!(set.num_volumes(), 3);
+        set.add_volume(1, "vol1-new".to_owned());
+        assert_eq!(set.volume_path(1), Some("vol1-new"));
+
+        set.set_manifest_path("manifest".to_owned());
+        assert!(set.is_complete());
+        assert_eq!(set.manifest_path(), "manifest");
+    }
+
     #[test]
     fn collection_status_display() {
         // NOTE: this is actually not a proper test
@@ -744,6 +1297,19 @@ mod test {
         println!("collection status:\n{}\n", display);
     }
 
+    #[test]
+    fn collection_status_display_full() {
+        let filenames = get_test_filenames();
+        let collection_status = Collections::from_filenames(&filenames);
+        let display = format!("{}", collection_status.display_full());
+        // the terse display is included
+        assert!(display.starts_with(&format!("{}", collection_status)));
+        for sig in &filenames[7..] {
+            assert!(display.contains(sig));
+        }
+        assert!(display.contains("duplicity-full-signatures.20150617T182545Z.sigtar.gz"));
+    }
+
     #[test]
     fn collection_status() {
         let filenames = get_test_filenames();
@@ -786,6 +1352,266 @@ mod test {
         }
     }
 
+    #[test]
+    fn validate_chains() {
+        let filenames = get_test_filenames();
+        let collection = Collections::from_filenames(&filenames);
+        assert_eq!(collection.validate(), vec![]);
+
+        // drop the last incremental signature
+        let collection = Collections::from_filenames(&filenames[..filenames.len() - 1]);
+        let sig = collection.signature_chains().next().unwrap();
+        let backup = collection.backup_chains().next().unwrap();
+        let expected = vec![
+            ValidationError::EndTimeMismatch {
+                sig: parse_time_str("20150617t182629z").unwrap(),
+                backup: parse_time_str("20150617t182650z").unwrap(),
+            },
+            ValidationError::CountMismatch {
+                sig_count: 1,
+                backup_count: 2,
+            },
+        ];
+        assert_eq!(sig.validate_against_backup_chain(backup), expected);
+        let expected = expected
+            .into_iter()
+            .map(|error| ValidationIssue::ChainMismatch { chain: 0, error })
+            .collect::<Vec<_>>();
+        assert_eq!(collection.validate(), expected);
+    }
+
+    #[test]
+    fn validate_missing_signatures() {
+        // drop all the signatures
+        let filenames = get_test_filenames()
+            .into_iter()
+            .filter(|f| !f.contains("signatures"))
+            .collect::<Vec<_>>();
+        let collection = Collections::from_filenames(&filenames);
+        assert_eq!(
+            collection.validate(),
+            vec![ValidationIssue::MissingSignatureChain(0)]
+        );
+    }
+
+    #[test]
+    fn prefixed_names() {
+        let filenames = get_test_filenames()
+            .into_iter()
+            .map(|f| format!("sub/{}", f))
+            .collect::<Vec<_>>();
+        let collection = Collections::from_filenames(&filenames);
+        assert_eq!(collection.backup_chains().count(), 1);
+        let chain = collection.backup_chains().next().unwrap();
+        assert_eq!(
+            chain.full_set().manifest_path(),
+            "sub/duplicity-full.20150617T182545Z.manifest"
+        );
+        assert_eq!(
+            chain.full_set().volume_path(1),
+            Some("sub/duplicity-full.20150617T182545Z.vol1.difftar.gz")
+        );
+        let sig_chain = collection.signature_chains().next().unwrap();
+        assert_eq!(
+            sig_chain.full_signature().file_name,
+            "sub/duplicity-full-signatures.20150617T182545Z.sigtar.gz"
+        );
+        assert!(sig_chain
+            .inc_signatures()
+            .all(|s| s.file_name.starts_with("sub/")));
+    }
+
+    #[test]
+    fn partial_manifests() {
+        let partial = "duplicity-inc.20150617T182629Z.to.20150617T182650Z.manifest.part";
+        let complete = "duplicity-inc.20150617T182629Z.to.20150617T182650Z.manifest";
+        // the complete manifest is preferred, whatever the order
+        for first in &[true, false] {
+            let mut filenames = get_test_filenames();
+            if *first {
+                filenames.insert(0, partial);
+            } else {
+                filenames.push(partial);
+            }
+            let collection = Collections::from_filenames(&filenames);
+            let chain = collection.backup_chains().next().unwrap();
+            let set = chain.inc_sets().last().unwrap();
+            assert!(!set.is_partial());
+            assert_eq!(set.manifest_path(), complete);
+        }
+
+        // only the partial manifest is available
+        let filenames = get_test_filenames()
+            .into_iter()
+            .map(|f| if f == complete { partial } else { f })
+            .collect::<Vec<_>>();
+        let collection = Collections::from_filenames(&filenames);
+        let chain = collection.backup_chains().next().unwrap();
+        let set = chain.inc_sets().last().unwrap();
+        assert!(set.is_partial());
+        assert_eq!(set.manifest_path(), partial);
+        assert!(chain.inc_sets().take(1).all(|s| !s.is_partial()));
+    }
+
+    #[test]
+    fn partial_signatures() {
+        let mut filenames = get_test_filenames();
+        filenames.push("duplicity-full-signatures.20150617T182545Z.sigtar.gz.part");
+        filenames
+            .push("duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.gz.part");
+        let collection = Collections::from_filenames(&filenames);
+        assert_eq!(collection.signature_chains().count(), 1);
+        let sig_chain = collection.signature_chains().next().unwrap();
+        assert!(!sig_chain.full_signature().partial);
+        assert_eq!(
+            sig_chain.full_signature().file_name,
+            "duplicity-full-signatures.20150617T182545Z.sigtar.gz"
+        );
+        assert_eq!(sig_chain.inc_signatures().count(), 2);
+        assert!(sig_chain.inc_signatures().all(|s| !s.partial));
+
+        // only the partial signature is available
+        let filenames = vec!["duplicity-full-signatures.20150617T182545Z.sigtar.gz.part"];
+        let collection = Collections::from_filenames(&filenames);
+        let sig_chain = collection.signature_chains().next().unwrap();
+        assert!(sig_chain.full_signature().partial);
+        assert!(sig_chain.full_signature().compressed);
+    }
+
+    #[test]
+    fn filename_iter() {
+        let filenames = get_test_filenames();
+        let from_vec = Collections::from_filenames(&filenames);
+        let from_iter = Collections::from_filename_iter(filenames.iter());
+        assert_eq!(format!("{:?}", from_iter), format!("{:?}", from_vec));
+    }
+
+    #[test]
+    fn filename_iter_large() {
+        const NUM_VOLUMES: usize = 20_000;
+        // names are generated lazily, interleaving many unrelated files
+        let volumes = (1..NUM_VOLUMES + 1).flat_map(|i| {
+            vec![
+                format!("duplicity-full.20150617T182545Z.vol{}.difftar.gz", i),
+                format!("unrelated-{}.txt", i),
+            ]
+        });
+        let others = vec![
+            "duplicity-full.20150617T182545Z.manifest".to_owned(),
+            "duplicity-full-signatures.20150617T182545Z.sigtar.gz".to_owned(),
+        ];
+        let collection = Collections::from_filename_iter(volumes.chain(others));
+        assert_eq!(collection.backup_chains().count(), 1);
+        assert_eq!(collection.signature_chains().count(), 1);
+        let full_set = collection.backup_chains().next().unwrap().full_set();
+        assert_eq!(
+            full_set.manifest_path(),
+            "duplicity-full.20150617T182545Z.manifest"
+        );
+        assert_eq!(
+            full_set.volume_path(NUM_VOLUMES),
+            Some("duplicity-full.20150617T182545Z.vol20000.difftar.gz")
+        );
+        assert!((1..NUM_VOLUMES + 1).all(|i| full_set.volume_path(i).is_some()));
+    }
+
+    #[test]
+    fn chain_time_queries() {
+        let filenames = get_test_filenames();
+        let collection = Collections::from_filenames(&filenames);
+        let chain = collection.backup_chains().next().unwrap();
+        let t = |s| parse_time_str(s).unwrap();
+        let nearest_end = |s| chain.snapshot_nearest_to(t(s)).end_time();
+
+        // boundaries
+        assert!(!chain.contains_snapshot_at(t("20150617t182544z")));
+        assert!(chain.contains_snapshot_at(t("20150617t182545z")));
+        assert!(chain.contains_snapshot_at(t("20150617t182600z")));
+        assert!(chain.contains_snapshot_at(t("20150617t182650z")));
+        assert!(!chain.contains_snapshot_at(t("20150617t182651z")));
+
+        // exact matches
+        assert!(chain.snapshot_nearest_to(t("20150617t182545z")).is_full());
+        assert_eq!(nearest_end("20150617t182629z"), t("20150617t182629z"));
+        assert_eq!(nearest_end("20150617t182650z"), t("20150617t182650z"));
+        // outside of the chain
+        assert!(chain.snapshot_nearest_to(t("20150101t000000z")).is_full());
+        assert_eq!(nearest_end("20160101t000000z"), t("20150617t182650z"));
+        // between incrementals
+        assert!(chain.snapshot_nearest_to(t("20150617t182600z")).is_full());
+        assert_eq!(nearest_end("20150617t182610z"), t("20150617t182629z"));
+        assert_eq!(nearest_end("20150617t182639z"), t("20150617t182629z"));
+        assert_eq!(nearest_end("20150617t182640z"), t("20150617t182650z"));
+    }
+
+    #[test]
+    fn set_at_time() {
+        let filenames = get_test_filenames();
+        let collection = Collections::from_filenames(&filenames);
+        let chain = collection.backup_chains().next().unwrap();
+        let t = |s| parse_time_str(s).unwrap();
+        let set_end = |s| chain.set_at_time(t(s)).unwrap().end_time();
+
+        // exact matches
+        assert!(chain.set_at_time(t("20150617t182545z")).unwrap().is_full());
+        assert_eq!(set_end("20150617t182629z"), t("20150617t182629z"));
+        assert_eq!(set_end("20150617t182650z"), t("20150617t182650z"));
+        // before the chain
+        assert!(chain.set_at_time(t("20150617t182544z")).is_none());
+        assert!(chain.set_at_time(t("20150101t000000z")).is_none());
+        // after the chain
+        assert_eq!(set_end("20160101t000000z"), t("20150617t182650z"));
+        // between sets
+        assert!(chain.set_at_time(t("20150617t182628z")).unwrap().is_full());
+        assert_eq!(set_end("20150617t182630z"), t("20150617t182629z"));
+        assert_eq!(set_end("20150617t182649z"), t("20150617t182629z"));
+    }
+
+    #[test]
+    fn nth_set() {
+        let filenames = get_test_filenames();
+        let collection = Collections::from_filenames(&filenames);
+        let chain = collection.backup_chains().next().unwrap();
+        assert_eq!(chain.num_sets(), 3);
+        let paths: Vec<_> = (0..chain.num_sets())
+            .map(|i| chain.nth_set(i).unwrap().manifest_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "duplicity-full.20150617T182545Z.manifest",
+                "duplicity-inc.20150617T182545Z.to.20150617T182629Z.manifest",
+                "duplicity-inc.20150617T182629Z.to.20150617T182650Z.manifest",
+            ]
+        );
+        assert!(chain.nth_set(3).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = Path::new(OsStr::from_bytes(
+            b"dir\xff/duplicity-full.20150617T182545Z.manifest",
+        ));
+        let mut filenames = get_test_filenames()
+            .into_iter()
+            .map(Path::new)
+            .collect::<Vec<_>>();
+        filenames.insert(3, invalid);
+        let collection = Collections::from_filenames(&filenames);
+        assert_eq!(collection.ignored_files(), &[invalid.to_owned()]);
+        // valid names are still recognized
+        let expected = Collections::from_filenames(get_test_filenames());
+        assert_eq!(
+            format!("{:?}", collection.backup_chains().collect::<Vec<_>>()),
+            format!("{:?}", expected.backup_chains().collect::<Vec<_>>())
+        );
+        assert_eq!(collection.signature_chains().count(), 1);
+    }
+
     #[test]
     fn multi_chain() {
         let fnames = vec![
@@ -816,4 +1642,124 @@ mod test {
         let chain = collection.signature_chains().nth(1).unwrap();
         assert_eq!(chain.inc_signatures().count(), 1);
     }
+
+    #[test]
+    fn default_empty() {
+        let collection = Collections::default();
+        assert_eq!(collection, Collections::new());
+        assert_eq!(collection.num_chains(), 0);
+        assert_eq!(collection.num_signature_chains(), 0);
+        assert!(collection.ignored_files().is_empty());
+    }
+
+    #[test]
+    fn indexed_chains() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let collection = Collections::from_filenames(backend.file_names().unwrap());
+        assert_eq!(collection.num_chains(), 2);
+        assert_eq!(collection.num_signature_chains(), 2);
+        for (i, chain) in collection.backup_chains().enumerate() {
+            let indexed = collection.backup_chain(i).unwrap();
+            assert_eq!(indexed.start_time(), chain.start_time());
+        }
+        for (i, chain) in collection.signature_chains().enumerate() {
+            let indexed = collection.signature_chain(i).unwrap();
+            assert_eq!(indexed.start_time(), chain.start_time());
+        }
+        assert_eq!(
+            collection
+                .backup_chain(1)
+                .unwrap()
+                .full_set()
+                .manifest_path(),
+            "duplicity-full.20160108T223209Z.manifest"
+        );
+        assert!(collection.backup_chain(2).is_none());
+        assert!(collection.signature_chain(2).is_none());
+    }
+
+    #[test]
+    fn full_backups() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let collection = Collections::from_filenames(backend.file_names().unwrap());
+        assert_eq!(
+            collection.oldest_full_backup().unwrap().manifest_path(),
+            "duplicity-full.20160108T223144Z.manifest"
+        );
+        assert_eq!(
+            collection.latest_full_backup().unwrap().manifest_path(),
+            "duplicity-full.20160108T223209Z.manifest"
+        );
+
+        let empty = Collections::from_filenames(Vec::<&str>::new());
+        assert!(empty.latest_full_backup().is_none());
+        assert!(empty.oldest_full_backup().is_none());
+    }
+
+    #[test]
+    fn all_sets_chronological() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let collection = Collections::from_filenames(backend.file_names().unwrap());
+        let sets: Vec<_> = collection.iter_all_backup_sets().collect();
+        assert_eq!(sets.len(), 4);
+        for (i, (index, _)) in sets.iter().enumerate() {
+            assert_eq!(i, *index);
+        }
+        assert!(sets
+            .windows(2)
+            .all(|w| w[0].1.end_time() < w[1].1.end_time()));
+        assert_eq!(
+            sets[2].1.manifest_path(),
+            "duplicity-full.20160108T223209Z.manifest"
+        );
+
+        let sigs: Vec<_> = collection.iter_all_signature_files().collect();
+        assert_eq!(sigs.len(), 4);
+        assert!(sigs.windows(2).all(|w| w[0].1.time < w[1].1.time));
+        assert_eq!(
+            sigs[2].1.file_name,
+            "duplicity-full-signatures.20160108T223209Z.sigtar.gz"
+        );
+    }
+
+    #[test]
+    fn all_sets_interleaved() {
+        // the incremental set of the first chain ends after the start of the second chain
+        let collection = Collections::from_filenames(vec![
+            "duplicity-full.20150617T100000Z.manifest",
+            "duplicity-full.20150617T100000Z.vol1.difftar.gz",
+            "duplicity-inc.20150617T100000Z.to.20150617T130000Z.manifest",
+            "duplicity-inc.20150617T100000Z.to.20150617T130000Z.vol1.difftar.gz",
+            "duplicity-full.20150617T120000Z.manifest",
+            "duplicity-full.20150617T120000Z.vol1.difftar.gz",
+        ]);
+        assert_eq!(collection.num_chains(), 2);
+        let paths: Vec<_> = collection
+            .iter_all_backup_sets()
+            .map(|(_, s)| s.manifest_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "duplicity-full.20150617T100000Z.manifest",
+                "duplicity-full.20150617T120000Z.manifest",
+                "duplicity-inc.20150617T100000Z.to.20150617T130000Z.manifest",
+            ]
+        );
+    }
+
+    #[test]
+    fn reuse_parser() {
+        let parser = FileNameParser::new();
+        for dir in &["single_vol", "multi_chain", "tree", "uncompressed"] {
+            let backend = LocalBackend::new(Path::new("tests/backups").join(dir));
+            let names = backend.file_names().unwrap().collect::<Vec<_>>();
+            assert_eq!(
+                Collections::from_filenames_with(&parser, &names),
+                Collections::from_filenames(&names),
+                "{}",
+                dir
+            );
+        }
+    }
 }
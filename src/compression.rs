@@ -0,0 +1,109 @@
+//! Selection of the decompressor for backup files.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
+
+use flate2::read::GzDecoder;
+
+/// The compression format of a backup file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// The file is not compressed.
+    None,
+    /// The gzip format, the default used by duplicity.
+    Gzip,
+    /// The bzip2 format.
+    Bzip2,
+    /// The zstd format.
+    Zstd,
+}
+
+impl Compression {
+    /// Determines the compression format by looking at the file name extension.
+    pub fn from_file_name(name: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".gz") || name.ends_with(".z") {
+            Compression::Gzip
+        } else if name.ends_with(".bz2") {
+            Compression::Bzip2
+        } else if name.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Returns whether this is an actual compression format.
+    pub fn is_compressed(self) -> bool {
+        self != Compression::None
+    }
+
+    /// Wraps the given input with the right decompressor.
+    ///
+    /// Returns an error if the support for the compression format has not been compiled in.
+    pub fn decoder<'a, R: Read + 'a>(self, input: R) -> io::Result<Box<dyn Read + 'a>> {
+        match self {
+            Compression::None => Ok(Box::new(input)),
+            Compression::Gzip => Ok(Box::new(GzDecoder::new(input))),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Ok(Box::new(bzip2::read::BzDecoder::new(input))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(input)?)),
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{:?} compression support is not enabled", self),
+            )),
+        }
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match *self {
+            Compression::None => "uncompressed",
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_file_name() {
+        let cases = vec![
+            (
+                "duplicity-full.20150617T182545Z.vol1.difftar.gz",
+                Compression::Gzip,
+            ),
+            (
+                "duplicity-full.20150617T182545Z.vol1.difftar.Z",
+                Compression::Gzip,
+            ),
+            (
+                "duplicity-full-signatures.20150617T182545Z.sigtar.bz2",
+                Compression::Bzip2,
+            ),
+            (
+                "duplicity-full-signatures.20150617T182545Z.sigtar.zst",
+                Compression::Zstd,
+            ),
+            (
+                "duplicity-full-signatures.20150617T182545Z.sigtar",
+                Compression::None,
+            ),
+            (
+                "duplicity-full.20150617T182545Z.manifest.gpg",
+                Compression::None,
+            ),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(Compression::from_file_name(name), expected, "{}", name);
+        }
+    }
+}
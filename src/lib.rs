@@ -42,33 +42,47 @@
 #![cfg_attr(feature = "lints", plugin(clippy))]
 
 extern crate byteorder;
+#[cfg(unix)]
+extern crate filetime;
 extern crate flate2;
+extern crate md5;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "regex")]
 extern crate regex;
+extern crate sha1_smol;
 extern crate tabwriter;
 extern crate tar;
 extern crate time;
 
+mod compression;
 mod macros;
 mod rawpath;
 
 pub mod backend;
 pub mod collections;
 pub mod manifest;
+pub mod pathparse;
 pub mod signatures;
 pub mod timefmt;
 
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell};
 use std::fmt::{self, Display, Formatter};
-use std::io;
-use std::ops::Deref;
-use std::path::Path;
+use std::io::{self, Read};
+use std::ops::{Deref, Range};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use time::Timespec;
 
 pub use backend::Backend;
+use backend::Decryptor;
 use collections::{BackupChain, BackupSet, Collections};
-use manifest::Manifest;
-use signatures::Chain;
+use compression::Compression;
+use manifest::{FileLocation, Manifest};
+use pathparse::PrefixKind;
+use signatures::{Chain, DiffKind};
+use timefmt::SortableTimeDisplay;
 
 /// A top level representation of a duplicity backup.
 #[derive(Debug)]
@@ -76,7 +90,29 @@ pub struct Backup<B> {
     backend: B,
     collections: Collections,
     signatures: Vec<RefCell<Option<Chain>>>,
+    // estimated size of the cached signature chains, zero if not loaded
+    signature_sizes: Vec<Cell<usize>>,
+    cache_size: Option<usize>,
     manifests: Vec<RefCell<Option<Manifest>>>,
+    decryptor: Option<DecryptorBox>,
+}
+
+/// A `Backup` shared by reference counting.
+///
+/// Clones share the same backup, with its cached signatures and manifests, and all the methods of
+/// `Backup` are available through `Deref`. Note that `Backup` uses `RefCell` internally to cache
+/// resources, so it is not `Sync`: a shared backup cannot be sent to other threads, and every
+/// thread needs to open its own `Backup`.
+pub type ArcBackup<B> = Arc<Backup<B>>;
+
+/// A builder to open a `Backup` with custom options.
+///
+/// Use `Backup::new` if the default options are fine.
+#[derive(Debug)]
+pub struct BackupBuilder<B> {
+    backend: Option<B>,
+    cache_size: Option<usize>,
+    decryptor: Option<DecryptorBox>,
 }
 
 /// Represents all the snapshots in a backup.
@@ -103,16 +139,75 @@ pub struct Snapshot<'a> {
     backup: &'a dyn ResourceCache,
 }
 
+/// The metadata of a snapshot, available without loading signatures or manifests.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SnapshotMetadata {
+    /// The time in which the snapshot has been taken.
+    ///
+    /// When serialized, the time is represented as seconds since the Unix epoch.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::timefmt::serialize_timespec")
+    )]
+    pub time: Timespec,
+    /// Whether the snapshot is a full backup.
+    pub is_full: bool,
+    /// The number of volumes contained in the snapshot.
+    pub num_volumes: usize,
+    /// The index of the backup chain containing the snapshot, starting from zero.
+    pub chain_index: usize,
+    /// The index of the snapshot in its backup chain, where zero is the full snapshot.
+    pub snapshot_index_in_chain: usize,
+}
+
 /// Contains the files present in a certain backup snapshot.
 pub struct SnapshotEntries<'a> {
     chain: Ref<'a, Option<Chain>>,
     sig_id: usize,
 }
 
+/// The signatures of all the snapshots in a backup chain.
+pub struct ChainSnapshots<'a> {
+    chain: Ref<'a, Option<Chain>>,
+}
+
+/// Changes in the files and directories between two snapshots of the same backup chain.
+pub struct SnapshotDiff<'a> {
+    chain: Ref<'a, Option<Chain>>,
+    from_id: usize,
+    to_id: usize,
+}
+
+/// The backup chains of a backup coming from a single source.
+///
+/// A source is identified by the hostname and the local directory recorded in the manifests.
+/// See `Backup::split_by_source`.
+pub struct BackupView<'a> {
+    backup: &'a dyn ResourceCache,
+    hostname: String,
+    local_dir: Option<PathBuf>,
+    chain_ids: Vec<usize>,
+}
+
 /// Reference to a Manifest.
 #[derive(Debug)]
 pub struct ManifestRef<'a>(Ref<'a, Option<Manifest>>);
 
+// a decryptor that can be stored in structs implementing `Debug`
+struct DecryptorBox(Box<dyn Decryptor>);
+
+// the files of a backup, decrypted when encrypted and a decryptor is available
+struct BackupFiles<'a, B> {
+    backend: &'a B,
+    decryptor: Option<&'a dyn Decryptor>,
+}
+
+enum BackupFile<R> {
+    Plain(R),
+    Decrypted(Box<dyn Read>),
+}
+
 struct CollectionsIter<'a> {
     chain_iter: collections::ChainIter<'a, BackupChain>,
     incset_iter: Option<collections::BackupSetIter<'a>>,
@@ -130,6 +225,8 @@ trait ResourceCache {
         chain_id: usize,
         manifest_path: &str,
     ) -> Result<Ref<Option<Manifest>>, manifest::ParseError>;
+    fn _file_size(&self, path: &str) -> io::Result<u64>;
+    fn _can_decrypt(&self) -> bool;
 }
 
 impl<B: Backend> Backup<B> {
@@ -151,20 +248,60 @@ impl<B: Backend> Backup<B> {
     /// println!("Got backup with {} snapshots!", backup.snapshots().unwrap().into_iter().count());
     /// ```
     pub fn new(backend: B) -> io::Result<Self> {
+        Self::with_options(backend, None, None)
+    }
+
+    /// Opens an existing backup, failing if some backup sets are partial.
+    ///
+    /// A backup set is partial when duplicity has been stopped before completing it, and only a
+    /// partial manifest is present. `Backup::new` accepts all the backup sets instead, and
+    /// reports partial ones through `Snapshot::is_partial`.
+    ///
+    /// # Errors
+    /// This function will return an `InvalidData` error if a partial backup set is found, or in
+    /// the same cases as `Backup::new`.
+    pub fn new_strict(backend: B) -> io::Result<Self> {
+        let backup = Self::new(backend)?;
+        for snapshot in backup.snapshots()? {
+            if snapshot.is_partial() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "The backup set {} is partial",
+                        snapshot.as_backup_set().manifest_path()
+                    ),
+                ));
+            }
+        }
+        Ok(backup)
+    }
+
+    fn with_options(
+        backend: B,
+        cache_size: Option<usize>,
+        decryptor: Option<DecryptorBox>,
+    ) -> io::Result<Self> {
         let files = backend.file_names()?;
-        let collections = Collections::from_filenames(files);
+        let collections = Collections::from_filename_iter(files);
         let signatures = collections
             .signature_chains()
             .map(|_| RefCell::new(None))
             .collect();
+        let signature_sizes = collections
+            .signature_chains()
+            .map(|_| Cell::new(0))
+            .collect();
         let manifests = (0..collections.num_snapshots())
             .map(|_| RefCell::new(None))
             .collect();
         Ok(Backup {
-            backend: backend,
-            collections: collections,
-            signatures: signatures,
-            manifests: manifests,
+            backend,
+            collections,
+            signatures,
+            signature_sizes,
+            cache_size,
+            manifests,
+            decryptor,
         })
     }
 
@@ -175,10 +312,281 @@ impl<B: Backend> Backup<B> {
         Ok(Snapshots { backup: self })
     }
 
+    /// Returns the snapshot with the given index, or `None` if there is no such snapshot.
+    ///
+    /// Snapshots are numbered starting from zero, in chronological order across all the backup
+    /// chains. Full and incremental snapshots are counted together, so the index is the same as
+    /// the position of the snapshot in `snapshots`.
+    pub fn snapshot(&self, index: usize) -> io::Result<Option<Snapshot<'_>>> {
+        Ok(self.snapshots()?.into_iter().nth(index))
+    }
+
+    /// Returns an iterator over the snapshots, starting from the one with the given index.
+    ///
+    /// The result is the same as `snapshots().into_iter().skip(index)`, but the starting
+    /// snapshot is found by looking at the length of the backup chains, without going through
+    /// the previous snapshots. An empty iterator is returned if the index is out of range.
+    pub fn snapshot_iter_from(&self, index: usize) -> io::Result<SnapshotsIter<'_>> {
+        let chains = self._collections().backup_chains().as_slice();
+        let mut first = index;
+        for (chain_id, chain) in chains.iter().enumerate() {
+            // the full set comes before the incremental ones
+            let num_sets = chain.inc_sets().len() + 1;
+            if first >= num_sets {
+                first -= num_sets;
+                continue;
+            }
+            let (chain_iter, chain_id, incset_iter, sig_id) = if first == 0 {
+                // the next chain to visit is the current one
+                (chains[chain_id..].iter(), chain_id, None, 0)
+            } else {
+                let incsets = &chain.inc_sets().as_slice()[first - 1..];
+                (
+                    chains[chain_id + 1..].iter(),
+                    chain_id + 1,
+                    Some(incsets.iter()),
+                    first - 1,
+                )
+            };
+            return Ok(SnapshotsIter {
+                set_iter: CollectionsIter {
+                    chain_iter,
+                    incset_iter,
+                },
+                chain_id,
+                sig_id,
+                man_id: index,
+                backup: self,
+            });
+        }
+        Ok(SnapshotsIter {
+            set_iter: CollectionsIter {
+                chain_iter: chains[chains.len()..].iter(),
+                incset_iter: None,
+            },
+            chain_id: chains.len(),
+            sig_id: 0,
+            man_id: index,
+            backup: self,
+        })
+    }
+
+    /// Returns the signatures of all the snapshots in the given backup chain.
+    ///
+    /// The signature chain is loaded once, so this is more efficient than calling
+    /// `Snapshot::entries` for each snapshot, when all the snapshots of a chain need to be
+    /// browsed. Chains are indexed starting from zero, in chronological order.
+    ///
+    /// # Errors
+    /// This function will return an error if the chain is not present, or its signatures cannot
+    /// be loaded.
+    pub fn chain_snapshots(&self, chain_id: usize) -> io::Result<ChainSnapshots<'_>> {
+        if chain_id >= self.signatures.len() {
+            return Err(not_found("The given backup chain is not present"));
+        }
+        let chain = self._signature_chain(chain_id)?;
+        Ok(ChainSnapshots { chain })
+    }
+
+    /// Returns the backup chain containing the given snapshot.
+    ///
+    /// The snapshot must belong to this backup.
+    ///
+    /// # Examples
+    /// ```
+    /// use ruplicity::Backup;
+    /// use ruplicity::backend::local::LocalBackend;
+    ///
+    /// let backend = LocalBackend::new("tests/backups/multi_chain");
+    /// let backup = Backup::new(backend).unwrap();
+    /// for snapshot in backup.snapshots().unwrap() {
+    ///     let chain = backup.chain_for_snapshot(&snapshot);
+    ///     println!("Snapshot in a chain with {} incremental sets", chain.inc_sets().count());
+    /// }
+    /// ```
+    pub fn chain_for_snapshot(&self, snapshot: &Snapshot<'_>) -> &BackupChain {
+        self.collections.backup_chain(snapshot.chain_id).unwrap()
+    }
+
+    /// Groups the backup chains by the source they come from.
+    ///
+    /// Duplicity allows backups of different directories, or different hosts, to be stored in the
+    /// same place. Since file names don't tell them apart, the manifest of the full snapshot of
+    /// each chain is read, and the chains are grouped by hostname and local directory. The views
+    /// are returned in order of their first chain.
+    ///
+    /// # Errors
+    /// This function will return an error if a manifest cannot be read or parsed.
+    ///
+    /// # Examples
+    /// ```
+    /// use ruplicity::Backup;
+    /// use ruplicity::backend::local::LocalBackend;
+    ///
+    /// let backend = LocalBackend::new("tests/backups/multi_chain");
+    /// let backup = Backup::new(backend).unwrap();
+    /// for view in backup.split_by_source().unwrap() {
+    ///     println!("{}: {} chains", view.hostname(), view.chain_ids().len());
+    /// }
+    /// ```
+    pub fn split_by_source(&self) -> io::Result<Vec<BackupView<'_>>> {
+        let mut views: Vec<BackupView<'_>> = Vec::new();
+        for snapshot in self.snapshots()?.into_iter().filter(|s| s.is_full()) {
+            let manifest = snapshot.manifest().map_err(manifest_to_io_error)?;
+            let local_dir = manifest.local_dir();
+            let pos = views.iter().position(|v| {
+                v.hostname == manifest.hostname() && v.local_dir.as_deref() == local_dir
+            });
+            match pos {
+                Some(pos) => views[pos].chain_ids.push(snapshot.chain_id),
+                None => views.push(BackupView {
+                    backup: self,
+                    hostname: manifest.hostname().to_owned(),
+                    local_dir: local_dir.map(Path::to_path_buf),
+                    chain_ids: vec![snapshot.chain_id],
+                }),
+            }
+        }
+        Ok(views)
+    }
+
+    /// Opens the file with the given path, as it was at the given time.
+    ///
+    /// The content is taken from the last snapshot not after the given time. As for
+    /// `Snapshot::can_read`, only the content stored in full, in a backup set that is not
+    /// encrypted or can be decrypted, can be read. The content is read in memory from the backup volumes.
+    ///
+    /// # Errors
+    /// An error of kind `NotFound` is returned if the path was not present at the given time,
+    /// `InvalidInput` if it was not a regular file, and `Unsupported` if its content cannot be
+    /// read.
+    pub fn open_file_at(&self, path: &[u8], time: Timespec) -> io::Result<impl Read> {
+        let snapshot = self
+            .snapshots()?
+            .into_iter()
+            .take_while(|s| s.time() <= time)
+            .last()
+            .ok_or_else(|| not_found("No snapshot is present at the given time"))?;
+        let entry_type = snapshot
+            .entries()?
+            .as_signature()
+            .find(|e| e.path_bytes() == path)
+            .map(|e| e.entry_type());
+        match entry_type {
+            Some(t) if t.is_file() => (),
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The path is not a regular file",
+                ))
+            }
+            None => return Err(not_found("The path is not present at the given time")),
+        }
+        let (set, volumes) = snapshot.content_location(path)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The content of the file cannot be read from the backup",
+            )
+        })?;
+        let mut content = Vec::new();
+        let mut found = false;
+        for i in volumes {
+            // the volume is present, as checked by `content_location`
+            let name = set.volume_path(i).unwrap();
+            let file = self.files().open_file(Path::new(name))?;
+            let decoder = Compression::from_file_name(name).decoder(file)?;
+            let mut archive = tar::Archive::new(decoder);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let matches = match pathparse::parse(&entry.path_bytes()) {
+                    Some((PrefixKind::Snapshot, p, _))
+                    | Some((PrefixKind::MultivolSnapshot, p, _)) => p == path,
+                    _ => false,
+                };
+                if matches {
+                    entry.read_to_end(&mut content)?;
+                    found = true;
+                }
+            }
+        }
+        if found {
+            Ok(io::Cursor::new(content))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The content of the file is missing from the backup volumes",
+            ))
+        }
+    }
+
     /// Unwraps this backup and returns the inner backend.
     pub fn into_inner(self) -> B {
         self.backend
     }
+
+    /// Moves this backup into a reference counted pointer, so it can be cheaply shared.
+    ///
+    /// See `ArcBackup` for the limitations of the shared backup.
+    pub fn into_arc(self) -> ArcBackup<B> {
+        Arc::new(self)
+    }
+}
+
+impl<B: Backend> BackupBuilder<B> {
+    /// Creates a builder with the default options and no backend.
+    pub fn new() -> Self {
+        BackupBuilder {
+            backend: None,
+            cache_size: None,
+            decryptor: None,
+        }
+    }
+
+    /// Sets the backend used to access the backup files.
+    ///
+    /// This option is mandatory.
+    pub fn backend(mut self, backend: B) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Sets the maximum size in bytes of the loaded signatures kept in memory.
+    ///
+    /// When a signature chain is loaded and the limit is exceeded, the other chains not currently
+    /// in use are discarded, and will be loaded again from the backend when needed. The size of
+    /// a chain is estimated, so this is not a strict bound. By default there is no limit, and the
+    /// signature chains are never discarded.
+    pub fn cache_size(mut self, bytes: usize) -> Self {
+        self.cache_size = Some(bytes);
+        self
+    }
+
+    /// Sets the decryptor used to read the encrypted files of the backup.
+    ///
+    /// Without a decryptor, the signatures, manifests and volumes of encrypted backup sets cannot
+    /// be read. By default there is no decryptor.
+    pub fn decryptor<D: Decryptor + 'static>(mut self, decryptor: D) -> Self {
+        self.decryptor = Some(DecryptorBox(Box::new(decryptor)));
+        self
+    }
+
+    /// Opens the backup with the given options.
+    ///
+    /// # Errors
+    /// This function will return an error if no backend has been set, or in the same cases as
+    /// `Backup::new`.
+    pub fn build(self) -> io::Result<Backup<B>> {
+        let backend = self.backend.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no backend has been set")
+        })?;
+        Backup::with_options(backend, self.cache_size, self.decryptor)
+    }
+}
+
+impl<B: Backend> Default for BackupBuilder<B> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Snapshots<'a> {
@@ -188,6 +596,32 @@ impl<'a> Snapshots<'a> {
     }
 }
 
+impl<'a> BackupView<'a> {
+    /// The hostname of the machine where the backups have been taken.
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// The original backup root path, if it was recorded.
+    pub fn local_dir(&self) -> Option<&Path> {
+        self.local_dir.as_deref()
+    }
+
+    /// The indexes of the backup chains belonging to this source, in increasing order.
+    pub fn chain_ids(&self) -> &[usize] {
+        &self.chain_ids
+    }
+
+    /// Returns an iterator over the snapshots belonging to this source.
+    pub fn snapshots(&self) -> impl Iterator<Item = Snapshot<'a>> + '_ {
+        Snapshots {
+            backup: self.backup,
+        }
+        .into_iter()
+        .filter(move |s| self.chain_ids.contains(&s.chain_id))
+    }
+}
+
 impl<'a> IntoIterator for Snapshots<'a> {
     type Item = Snapshot<'a>;
     type IntoIter = SnapshotsIter<'a>;
@@ -271,11 +705,111 @@ impl<'a> Snapshot<'a> {
         self.set.is_incremental()
     }
 
+    /// Returns whether the snapshot is partial.
+    ///
+    /// This could happen when a backup is stopped before the completion.
+    pub fn is_partial(&self) -> bool {
+        self.set.is_partial()
+    }
+
+    /// Returns whether the snapshot is complete.
+    ///
+    /// A snapshot is complete if it has a manifest that is not partial, and all its volumes are
+    /// present. A partial snapshot is never complete, but a snapshot can be neither partial nor
+    /// complete, e.g. when its manifest or some volumes are missing.
+    pub fn is_complete(&self) -> bool {
+        let mut volumes = 1..self.set.num_volumes();
+        self.set.is_complete()
+            && !self.set.is_partial()
+            && !volumes.is_empty()
+            && volumes.all(|i| self.set.volume_path(i).is_some())
+    }
+
     /// Returns the number of volumes contained in the snapshot.
     pub fn num_volumes(&self) -> usize {
         self.set.num_volumes()
     }
 
+    // the number of volume files present for the snapshot, since the volumes of a set are
+    // numbered starting from one
+    fn volume_count(&self) -> usize {
+        (1..self.set.num_volumes())
+            .filter(|&i| self.set.volume_path(i).is_some())
+            .count()
+    }
+
+    /// Returns the size in bytes of the volumes of this snapshot.
+    ///
+    /// For an incremental snapshot, this is the storage taken by the changes since the previous
+    /// snapshot. The sizes are taken from the backend metadata when available, otherwise the
+    /// volumes are read to count their bytes.
+    pub fn incremental_size(&self) -> io::Result<u64> {
+        let mut size = 0;
+        for i in 0..self.set.num_volumes() {
+            if let Some(path) = self.set.volume_path(i) {
+                size += self.backup._file_size(path)?;
+            }
+        }
+        Ok(size)
+    }
+
+    /// Returns the size in bytes of the volumes needed to restore this snapshot.
+    ///
+    /// This is the sum of the `incremental_size` of the snapshots in the chain, from the full
+    /// snapshot up to this one.
+    pub fn cumulative_size(&self) -> io::Result<u64> {
+        let chain = self
+            .backup
+            ._collections()
+            .backup_chain(self.chain_id)
+            .unwrap();
+        let first_id = self.man_id - self.sig_id;
+        let mut size = 0;
+        for i in 0..=self.sig_id {
+            let snapshot = Snapshot {
+                set: chain.nth_set(i).unwrap(),
+                chain_id: self.chain_id,
+                sig_id: i,
+                man_id: first_id + i,
+                backup: self.backup,
+            };
+            size += snapshot.incremental_size()?;
+        }
+        Ok(size)
+    }
+
+    /// Returns the metadata of the snapshot.
+    ///
+    /// Unlike `entries` and `manifest`, this does not perform any I/O.
+    pub fn metadata(&self) -> SnapshotMetadata {
+        SnapshotMetadata {
+            time: self.time(),
+            is_full: self.is_full(),
+            num_volumes: self.volume_count(),
+            chain_index: self.chain_id,
+            snapshot_index_in_chain: self.sig_id,
+        }
+    }
+
+    /// Returns a summary of the snapshot metadata, in JSON format.
+    ///
+    /// This does not require any serialization library. The time is in UTC, and the chain id is
+    /// the index of the backup chain containing the snapshot.
+    ///
+    /// # Example
+    /// ```text
+    /// {"time":"2015-06-17T18:25:45Z","is_full":true,"num_volumes":1,"chain_id":0}
+    /// ```
+    pub fn to_json_summary(&self) -> String {
+        format!(
+            "{{\"time\":\"{}\",\"is_full\":{},\"num_volumes\":{},\"chain_id\":{}}}",
+            self.time().into_sortable_display(),
+            self.is_full(),
+            self.volume_count(),
+            self.chain_id
+        )
+    }
+
     /// Returns the low level representation of the snapshot.
     pub fn as_backup_set(&self) -> &'a BackupSet {
         self.set
@@ -298,6 +832,110 @@ impl<'a> Snapshot<'a> {
         }
     }
 
+    /// Returns the changes in files and directories from this snapshot to the other one.
+    ///
+    /// The snapshots must belong to the same backup chain, because the comparison is done by
+    /// using the chain signatures, loaded as in `entries`.
+    ///
+    /// # Errors
+    /// An error of kind `InvalidInput` is returned if the snapshots belong to different chains.
+    pub fn diff(&self, other: &Snapshot<'_>) -> io::Result<SnapshotDiff<'a>> {
+        if self.chain_id != other.chain_id {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The snapshots belong to different backup chains",
+            ));
+        }
+        let sig = self.backup._signature_chain(self.chain_id)?;
+        let num_snapshots = sig.as_ref().unwrap().snapshots().len();
+        if self.sig_id < num_snapshots && other.sig_id < num_snapshots {
+            Ok(SnapshotDiff {
+                chain: sig,
+                from_id: self.sig_id,
+                to_id: other.sig_id,
+            })
+        } else {
+            Err(not_found("The signature chain is incomplete"))
+        }
+    }
+
+    /// Returns whether the content of the entry with the given path can be read from the backup.
+    ///
+    /// The content is readable only if it has been stored in full, in a backup set that is not
+    /// encrypted or can be decrypted, and all the volumes containing it are present. Content stored as a difference
+    /// from a previous version is not readable. Returns `false` if the entry is not present in
+    /// the snapshot.
+    ///
+    /// This needs to load the signatures, as in `entries`, and the manifest of the backup set
+    /// containing the content.
+    pub fn can_read(&self, path: &[u8]) -> io::Result<bool> {
+        Ok(self.content_location(path)?.is_some())
+    }
+
+    // returns the backup set storing the content of the given path in full, and the range of its
+    // volumes containing it, if the content is readable
+    fn content_location(&self, path: &[u8]) -> io::Result<Option<(&'a BackupSet, Range<usize>)>> {
+        if self.set.is_encrypted() && !self.backup._can_decrypt() {
+            return Ok(None);
+        }
+        // find the snapshot in the chain storing the content, and how it was stored
+        let (index, full) = {
+            let sig = self.backup._signature_chain(self.chain_id)?;
+            let chain = sig.as_ref().unwrap();
+            match chain.last_change(path, self.sig_id as u8) {
+                Some(0) => (0, true),
+                Some(i) => (i as usize, chain.last_change(path, i - 1).is_none()),
+                None => return Ok(None),
+            }
+        };
+        if !full {
+            return Ok(None);
+        }
+        let backup_chain = self
+            .backup
+            ._collections()
+            .backup_chain(self.chain_id)
+            .unwrap();
+        let set = match index {
+            0 => backup_chain.full_set(),
+            i => match backup_chain.inc_sets().nth(i - 1) {
+                Some(set) => set,
+                None => return Ok(None),
+            },
+        };
+        if set.is_encrypted() && !self.backup._can_decrypt() {
+            return Ok(None);
+        }
+        let man_id = self.man_id - self.sig_id + index;
+        let manifest = self
+            .backup
+            ._manifest(man_id, set.manifest_path())
+            .map_err(manifest_to_io_error)?;
+        let manifest = manifest.as_ref().unwrap();
+        let volumes = match (
+            manifest.first_volume_of_path(path),
+            manifest.last_volume_of_path(path),
+        ) {
+            (Some(first), Some(last)) => first..last + 1,
+            _ => return Ok(None),
+        };
+        if volumes.clone().all(|i| set.volume_path(i).is_some()) {
+            Ok(Some((set, volumes)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the volumes of this snapshot containing the given path, if present.
+    ///
+    /// This allows to know which volume files are needed to restore a path, without reading
+    /// them. Only the manifest of the snapshot is loaded. Note that for an incremental snapshot,
+    /// only the paths changed in the snapshot are stored in its volumes.
+    pub fn locate(&self, path: &[u8]) -> io::Result<Option<FileLocation>> {
+        let manifest = self.manifest().map_err(manifest_to_io_error)?;
+        Ok(manifest.locate_path(path))
+    }
+
     /// Returns the manifest for this snapshot.
     ///
     /// The relative manifest file is read on demand and cached for subsequent uses.
@@ -307,6 +945,20 @@ impl<'a> Snapshot<'a> {
                 ._manifest(self.man_id, self.set.manifest_path())?,
         ))
     }
+
+    /// Returns the hostname of the machine where the snapshot has been taken.
+    ///
+    /// The hostname is read from the manifest, that is loaded on demand and cached.
+    pub fn hostname(&self) -> Result<String, manifest::ParseError> {
+        Ok(self.manifest()?.hostname().to_owned())
+    }
+
+    /// Returns the original backup root path, if it was recorded.
+    ///
+    /// The path is read from the manifest, that is loaded on demand and cached.
+    pub fn source_dir(&self) -> Result<Option<PathBuf>, manifest::ParseError> {
+        Ok(self.manifest()?.local_dir().map(Path::to_path_buf))
+    }
 }
 
 impl<'a> SnapshotEntries<'a> {
@@ -322,6 +974,48 @@ impl<'a> SnapshotEntries<'a> {
             .unwrap()
             .files()
     }
+
+    /// Returns the logical size in bytes of the files in the snapshot.
+    ///
+    /// This is the size the files would occupy once restored, not the size of the backup
+    /// volumes. It is the sum of the upper bounds of the size hints, so it could be slightly
+    /// overestimated. Directories are not counted.
+    pub fn total_logical_size(self) -> u64 {
+        self.as_signature()
+            .filter(|e| !e.entry_type().is_dir())
+            .filter_map(|e| e.size_hint())
+            .map(|(_, upper)| upper as u64)
+            .sum()
+    }
+
+    /// Serializes the entries of the snapshot to a JSON array.
+    ///
+    /// See `signatures::SnapshotEntries::to_json` for the format.
+    #[cfg(feature = "serde")]
+    pub fn to_json(self) -> String {
+        self.as_signature().to_json()
+    }
+}
+
+impl<'a> ChainSnapshots<'a> {
+    /// Returns an iterator over the snapshots signatures, in chronological order.
+    pub fn iter(&self) -> signatures::Snapshots<'_> {
+        self.as_signature_chain().snapshots()
+    }
+
+    /// Returns the low level representation of the signature chain.
+    pub fn as_signature_chain(&self) -> &Chain {
+        self.chain.as_ref().unwrap()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b ChainSnapshots<'a> {
+    type Item = signatures::Snapshot<'b>;
+    type IntoIter = signatures::Snapshots<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl<'a> Display for SnapshotEntries<'a> {
@@ -330,6 +1024,42 @@ impl<'a> Display for SnapshotEntries<'a> {
     }
 }
 
+impl<'a> SnapshotDiff<'a> {
+    /// Returns the entries present only in the newer snapshot.
+    pub fn added(&self) -> impl Iterator<Item = signatures::Entry<'_>> {
+        self.as_signature().filter_map(|change| match change.kind {
+            DiffKind::Added => change.new,
+            _ => None,
+        })
+    }
+
+    /// Returns the entries present only in the older snapshot.
+    pub fn removed(&self) -> impl Iterator<Item = signatures::Entry<'_>> {
+        self.as_signature().filter_map(|change| match change.kind {
+            DiffKind::Removed => change.old,
+            _ => None,
+        })
+    }
+
+    /// Returns the entries present in both the snapshots, but changed.
+    ///
+    /// The entries are returned as they are in the newer snapshot.
+    pub fn modified(&self) -> impl Iterator<Item = signatures::Entry<'_>> {
+        self.as_signature().filter_map(|change| match change.kind {
+            DiffKind::Modified => change.new,
+            _ => None,
+        })
+    }
+
+    /// Returns the signatures representation for the changes.
+    pub fn as_signature(&self) -> signatures::SnapshotDiff<'_> {
+        self.chain
+            .as_ref()
+            .unwrap()
+            .diff_snapshots(self.from_id as u8, self.to_id as u8)
+    }
+}
+
 impl<'a> Deref for ManifestRef<'a> {
     type Target = Manifest;
 
@@ -344,24 +1074,23 @@ impl<B: Backend> ResourceCache for Backup<B> {
     }
 
     fn _signature_chain(&self, chain_id: usize) -> io::Result<Ref<Option<Chain>>> {
-        {
-            // check if there is a cached value
-            let mut sig = self.signatures[chain_id].borrow_mut();
-            if sig.is_none() {
-                // compute signatures now
-                if let Some(sigchain) = self.collections.signature_chains().nth(chain_id) {
-                    let new_sig = Chain::from_sigchain(sigchain, &self.backend)?;
-                    *sig = Some(new_sig);
-                } else {
-                    return Err(not_found(
-                        "The given backup snapshot does not have a \
-                                         corresponding signature",
-                    ));
-                }
+        // check if there is a cached value
+        // borrow mutably only if needed, because the cached value could be in use
+        if self.signatures[chain_id].borrow().is_none() {
+            // compute signatures now
+            if let Some(sigchain) = self.collections.signature_chain(chain_id) {
+                let new_sig = Chain::from_sigchain(sigchain, &self.files())?;
+                self.signature_sizes[chain_id].set(new_sig.approx_size_bytes());
+                *self.signatures[chain_id].borrow_mut() = Some(new_sig);
+            } else {
+                return Err(not_found(
+                    "The given backup snapshot does not have a \
+                                     corresponding signature",
+                ));
             }
+            self.shrink_signatures(chain_id);
         }
 
-        // need to close previous scope to borrow again
         // return the cached value
         Ok(self.signatures[chain_id].borrow())
     }
@@ -371,26 +1100,115 @@ impl<B: Backend> ResourceCache for Backup<B> {
         id: usize,
         path: &str,
     ) -> Result<Ref<Option<Manifest>>, manifest::ParseError> {
-        {
-            // check if there is a cached value
-            let mut sig = self.manifests[id].borrow_mut();
-            if sig.is_none() {
-                // compute manifest now
-                let mut file = io::BufReader::new(self.backend.open_file(Path::new(path))?);
-                *sig = Some(Manifest::parse(&mut file)?);
-            }
+        // check if there is a cached value
+        // borrow mutably only if needed, because the cached value could be in use
+        if self.manifests[id].borrow().is_none() {
+            // compute manifest now
+            let manifest = Manifest::reparse_from_backend(&self.files(), path)?;
+            *self.manifests[id].borrow_mut() = Some(manifest);
         }
 
-        // need to close previous scope to borrow again
         // return the cached value
         Ok(self.manifests[id].borrow())
     }
+
+    fn _file_size(&self, path: &str) -> io::Result<u64> {
+        let path = Path::new(path);
+        match self.backend.file_metadata(path)?.size_bytes {
+            Some(size) => Ok(size),
+            None => io::copy(&mut self.backend.open_file(path)?, &mut io::sink()),
+        }
+    }
+
+    fn _can_decrypt(&self) -> bool {
+        self.decryptor.is_some()
+    }
+}
+
+impl<B: Backend> Backup<B> {
+    // the backup files, decrypted if needed
+    fn files(&self) -> BackupFiles<'_, B> {
+        BackupFiles {
+            backend: &self.backend,
+            decryptor: self.decryptor.as_ref().map(|d| &*d.0),
+        }
+    }
+
+    // discards the cached signature chains, except the given one and the ones in use, until the
+    // cache size limit is respected
+    fn shrink_signatures(&self, keep_id: usize) {
+        let max_size = match self.cache_size {
+            Some(size) => size,
+            None => return,
+        };
+        let mut total: usize = self.signature_sizes.iter().map(Cell::get).sum();
+        let cached = self
+            .signatures
+            .iter()
+            .zip(&self.signature_sizes)
+            .enumerate();
+        for (id, (sig, size)) in cached {
+            if total <= max_size {
+                break;
+            }
+            if id == keep_id || size.get() == 0 {
+                continue;
+            }
+            if let Ok(mut sig) = sig.try_borrow_mut() {
+                *sig = None;
+                total -= size.get();
+                size.set(0);
+            }
+        }
+    }
+}
+
+impl<'a, B: Backend> Backend for BackupFiles<'a, B> {
+    type FileName = B::FileName;
+    type FileNameIter = B::FileNameIter;
+    type FileStream = BackupFile<B::FileStream>;
+
+    fn file_names(&self) -> io::Result<Self::FileNameIter> {
+        self.backend.file_names()
+    }
+
+    fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+        let mut file = self.backend.open_file(name)?;
+        match self.decryptor {
+            Some(d) if name.to_str().is_some_and(collections::is_encrypted) => {
+                Ok(BackupFile::Decrypted(d.decrypt(&mut file)?))
+            }
+            _ => Ok(BackupFile::Plain(file)),
+        }
+    }
+}
+
+impl<R: Read> Read for BackupFile<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            BackupFile::Plain(ref mut r) => r.read(buf),
+            BackupFile::Decrypted(ref mut r) => r.read(buf),
+        }
+    }
+}
+
+impl fmt::Debug for DecryptorBox {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("Decryptor")
+    }
 }
 
 fn not_found(msg: &str) -> io::Error {
     io::Error::new(io::ErrorKind::NotFound, msg)
 }
 
+fn manifest_to_io_error(err: manifest::ParseError) -> io::Error {
+    match err {
+        manifest::ParseError::Io(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -401,7 +1219,7 @@ mod test {
     use signatures::{Chain, Entry};
     use timefmt::parse_time_str;
 
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::io::BufReader;
     use std::path::Path;
     use time::Timespec;
@@ -476,6 +1294,23 @@ mod test {
             .collect()
     }
 
+    // a backend exposing only file names, that cannot open files
+    struct NamesBackend(Vec<&'static str>);
+
+    impl Backend for NamesBackend {
+        type FileName = &'static str;
+        type FileNameIter = Vec<&'static str>;
+        type FileStream = io::Empty;
+
+        fn file_names(&self) -> io::Result<Self::FileNameIter> {
+            Ok(self.0.clone())
+        }
+
+        fn open_file(&self, _name: &Path) -> io::Result<Self::FileStream> {
+            Err(not_found("file not available"))
+        }
+    }
+
     fn single_vol_signature_chain() -> Chain {
         let backend = LocalBackend::new("tests/backups/single_vol");
         let filenames = backend.file_names().unwrap();
@@ -533,6 +1368,349 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn partial_snapshots() {
+        let backend = NamesBackend(vec![
+            "duplicity-full.20150617T182545Z.manifest.part",
+            "duplicity-full.20150617T182545Z.vol1.difftar.gz",
+            "duplicity-inc.20150617T182545Z.to.20150617T182629Z.vol1.difftar.gz",
+        ]);
+        let backup = Backup::new(backend).unwrap();
+        let actual = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.is_partial(), s.is_complete()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![(true, false), (false, false)]);
+
+        // a manifest without volumes
+        let backend = NamesBackend(vec!["duplicity-full.20150617T182545Z.manifest"]);
+        let backup = Backup::new(backend).unwrap();
+        let snapshot = backup.snapshots().unwrap().into_iter().next().unwrap();
+        assert!(!snapshot.is_partial());
+        assert!(!snapshot.is_complete());
+
+        let backup = Backup::new(LocalBackend::new("tests/backups/single_vol")).unwrap();
+        assert!(backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .all(|s| s.is_complete()));
+    }
+
+    #[test]
+    fn snapshot_by_index() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let expected = vec![
+            ("20160108t223144z", true),
+            ("20160108t223159z", false),
+            ("20160108t223209z", true),
+            ("20160108t223217z", false),
+        ];
+        for (i, (time, full)) in expected.into_iter().enumerate() {
+            let snapshot = backup.snapshot(i).unwrap().unwrap();
+            assert_eq!(snapshot.time(), parse_time_str(time).unwrap());
+            assert_eq!(snapshot.is_full(), full);
+        }
+        assert!(backup.snapshot(4).unwrap().is_none());
+    }
+
+    #[test]
+    fn chain_for_snapshot() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let actual = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                let chain = backup.chain_for_snapshot(&s);
+                assert!(chain.start_time() <= s.time() && s.time() <= chain.end_time());
+                chain.start_time()
+            })
+            .collect::<Vec<_>>();
+        let t1 = parse_time_str("20160108t223144z").unwrap();
+        let t2 = parse_time_str("20160108t223209z").unwrap();
+        assert_eq!(actual, vec![t1, t1, t2, t2]);
+    }
+
+    #[test]
+    fn chain_snapshots() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let counts = (0..2)
+            .map(|id| {
+                let snapshots = backup.chain_snapshots(id).unwrap();
+                snapshots
+                    .iter()
+                    .map(|s| s.files().count())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![vec![2, 2], vec![2, 2]]);
+        assert!(backup.chain_snapshots(2).is_err());
+
+        // the same entries are returned by the single snapshots
+        let snapshots = backup.chain_snapshots(1).unwrap();
+        let actual = snapshots
+            .into_iter()
+            .map(|s| {
+                s.files()
+                    .map(|f| EntryTest::from_entry(&f))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(actual, from_backup(&backup)[2..]);
+    }
+
+    #[test]
+    fn snapshot_diff() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let paths = |entries: &mut dyn Iterator<Item = Entry>| {
+            entries
+                .map(|e| e.path().unwrap().to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        for &(from, to) in &[(0, 1), (2, 3)] {
+            let diff = backup
+                .snapshot(from)
+                .unwrap()
+                .unwrap()
+                .diff(&backup.snapshot(to).unwrap().unwrap())
+                .unwrap();
+            assert!(diff.added().next().is_none());
+            assert!(diff.removed().next().is_none());
+            assert_eq!(paths(&mut diff.modified()), vec!["", "file"]);
+        }
+        // snapshots in different chains cannot be compared
+        let err = backup
+            .snapshot(1)
+            .unwrap()
+            .unwrap()
+            .diff(&backup.snapshot(2).unwrap().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let diff = backup
+            .snapshot(0)
+            .unwrap()
+            .unwrap()
+            .diff(&backup.snapshot(1).unwrap().unwrap())
+            .unwrap();
+        assert_eq!(
+            paths(&mut diff.added()),
+            vec!["executable2/another_file", "new_file"]
+        );
+        assert_eq!(
+            paths(&mut diff.removed()),
+            vec!["deleted_file", "directory_to_file/file"]
+        );
+        assert_eq!(
+            paths(&mut diff.modified()),
+            vec![
+                "",
+                "changeable_permission",
+                "directory_to_file",
+                "executable2",
+                "file_to_directory",
+                "largefile",
+                "regular_file",
+                "symbolic_link",
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_iter_from() {
+        for dir in &["tests/backups/single_vol", "tests/backups/multi_chain"] {
+            let backup = Backup::new(LocalBackend::new(dir)).unwrap();
+            let ids = |iter: &mut dyn Iterator<Item = Snapshot>| {
+                iter.map(|s| (s.metadata(), s.man_id, s.set.manifest_path().to_owned()))
+                    .collect::<Vec<_>>()
+            };
+            let num_snapshots = backup.snapshots().unwrap().into_iter().count();
+            for i in 0..num_snapshots + 2 {
+                let expected = ids(&mut backup.snapshots().unwrap().into_iter().skip(i));
+                let actual = ids(&mut backup.snapshot_iter_from(i).unwrap());
+                assert_eq!(actual, expected, "index {} in {}", i, dir);
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_metadata() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let actual = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                let m = s.metadata();
+                assert_eq!(m.time, s.time());
+                (
+                    m.is_full,
+                    m.num_volumes,
+                    m.chain_index,
+                    m.snapshot_index_in_chain,
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (true, 1, 0, 0),
+            (false, 1, 0, 1),
+            (true, 1, 1, 0),
+            (false, 1, 1, 1),
+        ];
+        assert_eq!(actual, expected);
+
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let metadata = backup.snapshot(0).unwrap().unwrap().metadata();
+        assert!(metadata.is_full);
+        assert_eq!(metadata.num_volumes, 1);
+    }
+
+    #[test]
+    fn json_summary() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        let actual = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.to_json_summary())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            r#"{"time":"2016-01-08T22:31:44Z","is_full":true,"num_volumes":1,"chain_id":0}"#,
+            r#"{"time":"2016-01-08T22:31:59Z","is_full":false,"num_volumes":1,"chain_id":0}"#,
+            r#"{"time":"2016-01-08T22:32:09Z","is_full":true,"num_volumes":1,"chain_id":1}"#,
+            r#"{"time":"2016-01-08T22:32:17Z","is_full":false,"num_volumes":1,"chain_id":1}"#,
+        ];
+        assert_eq!(actual, expected);
+
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let snapshot = backup.snapshot(0).unwrap().unwrap();
+        assert_eq!(
+            snapshot.to_json_summary(),
+            r#"{"time":"2015-06-17T18:25:45Z","is_full":true,"num_volumes":1,"chain_id":0}"#
+        );
+    }
+
+    #[test]
+    fn can_read() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let can_read =
+            |i, path: &[u8]| backup.snapshot(i).unwrap().unwrap().can_read(path).unwrap();
+        // stored in the full backup
+        assert!(can_read(0, b"regular_file"));
+        assert!(can_read(0, b"deleted_file"));
+        // stored in full in the incremental backup, since it is new
+        assert!(can_read(1, b"new_file"));
+        // unchanged since the full backup
+        assert!(can_read(2, b"executable"));
+        // stored as a difference from the previous version
+        assert!(!can_read(1, b"regular_file"));
+        // not present
+        assert!(!can_read(1, b"deleted_file"));
+        assert!(!can_read(0, b"not_existing"));
+    }
+
+    #[test]
+    fn open_file_at() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        // between the first incremental snapshot and the second one
+        let time = parse_time_str("20150617t182640z").unwrap();
+        let read = |path: &[u8], time| {
+            let mut content = Vec::new();
+            backup
+                .open_file_at(path, time)?
+                .read_to_end(&mut content)
+                .map(|_| content)
+        };
+        let error_kind = |path: &[u8], time| read(path, time).unwrap_err().kind();
+
+        // stored in the full snapshot
+        let content = read(b"executable", time).unwrap();
+        assert_eq!(content.len(), 30);
+        assert!(content.starts_with(b"#!"));
+        // stored in multiple blocks in the full snapshot
+        let content = read(b"largefile", parse_time_str("20150617t182545z").unwrap()).unwrap();
+        assert_eq!(content.len(), 3_500_000);
+        // added by the first incremental snapshot
+        let content = read(b"executable2/another_file", time).unwrap();
+        assert_eq!(content.len(), 13);
+        assert_eq!(read(b"new_file", time).unwrap().len(), 6);
+
+        // stored as a difference
+        assert_eq!(
+            error_kind(b"regular_file", time),
+            io::ErrorKind::Unsupported
+        );
+        // not a file
+        assert_eq!(
+            error_kind(b"executable2", time),
+            io::ErrorKind::InvalidInput
+        );
+        // not present at that time
+        let full_time = parse_time_str("20150617t182600z").unwrap();
+        assert_eq!(error_kind(b"new_file", full_time), io::ErrorKind::NotFound);
+        let before = parse_time_str("20150101t000000z").unwrap();
+        assert_eq!(error_kind(b"executable", before), io::ErrorKind::NotFound);
+        assert_eq!(error_kind(b"missing", time), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn locate() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let snapshot = backup.snapshot(1).unwrap().unwrap();
+        let location = snapshot.locate(b"symbolic_link").unwrap().unwrap();
+        assert_eq!(location.first_volume, 1);
+        assert_eq!(location.last_volume, 1);
+        assert_eq!(location.first_volume_end_block, None);
+        assert_eq!(snapshot.locate(b"zzz").unwrap(), None);
+    }
+
+    #[test]
+    fn can_read_encrypted() {
+        let backend = NamesBackend(vec![
+            "duplicity-full.20150617T182545Z.manifest.gpg",
+            "duplicity-full.20150617T182545Z.vol1.difftar.gpg",
+            "duplicity-full-signatures.20150617T182545Z.sigtar.gpg",
+        ]);
+        let backup = Backup::new(backend).unwrap();
+        let snapshot = backup.snapshot(0).unwrap().unwrap();
+        assert!(!snapshot.can_read(b"regular_file").unwrap());
+    }
+
+    #[test]
+    fn total_logical_size() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let backup = Backup::new(backend).unwrap();
+        let actual = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.entries().unwrap().total_logical_size())
+            .collect::<Vec<_>>();
+        // sums of the real file sizes, the upper bounds of the size hints are at most one
+        // block of 512 bytes bigger for each file
+        let expected = [(3_576_614, 8), (3_576_640, 9), (3_576_188, 7)];
+        for (actual, &(size, num_files)) in actual.into_iter().zip(expected.iter()) {
+            assert!(actual >= size && actual <= size + num_files * 512);
+        }
+    }
+
     #[test]
     fn same_files() {
         let sigchain = single_vol_signature_chain();
@@ -544,6 +1722,52 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn uncompressed_files() {
+        let sigchain = single_vol_signature_chain();
+        let expected = from_sigchain(&sigchain);
+
+        let backend = LocalBackend::new("tests/backups/uncompressed");
+        let backup = Backup::new(backend).unwrap();
+        let coll = backup.snapshots().unwrap().as_collections();
+        let sigchain = coll.signature_chains().next().unwrap();
+        assert!(!sigchain.full_signature().compressed);
+        assert!(sigchain.inc_signatures().all(|s| !s.compressed));
+        let actual = from_backup(&backup);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn uncompressed_volumes() {
+        let backup = Backup::new(LocalBackend::new("tests/backups/uncompressed")).unwrap();
+        let plain = Backup::new(LocalBackend::new("tests/backups/single_vol")).unwrap();
+        let backend = LocalBackend::new("tests/backups/uncompressed");
+        for snapshot in backup.snapshots().unwrap() {
+            let set = snapshot.as_backup_set();
+            assert!(!set.is_compressed());
+            let results = snapshot
+                .manifest()
+                .unwrap()
+                .validate_volumes_hash(&backend, set);
+            assert_eq!(results, vec![manifest::VolumeHashResult::Ok(1)]);
+        }
+        let read = |backup: &Backup<LocalBackend>, path: &[u8], time| {
+            let mut content = Vec::new();
+            let mut file = backup
+                .open_file_at(path, parse_time_str(time).unwrap())
+                .unwrap();
+            file.read_to_end(&mut content).unwrap();
+            content
+        };
+        for (path, time) in &[
+            (&b"executable"[..], "20150617t182545z"),
+            (b"largefile", "20150617t182545z"),
+            (b"new_file", "20150617t182640z"),
+        ] {
+            assert_eq!(read(&backup, path, time), read(&plain, path, time));
+        }
+    }
+
     #[test]
     fn multi_chain_files() {
         let backend = LocalBackend::new("tests/backups/multi_chain");
@@ -574,6 +1798,115 @@ mod test {
         }
     }
 
+    #[test]
+    fn builder_cache_size() {
+        let expected =
+            from_backup(&Backup::new(LocalBackend::new("tests/backups/multi_chain")).unwrap());
+        let backup = BackupBuilder::new()
+            .backend(LocalBackend::new("tests/backups/multi_chain"))
+            .cache_size(1)
+            .build()
+            .unwrap();
+        assert_eq!(from_backup(&backup), expected);
+        // only the last used chain is kept in memory
+        let cached = backup.signatures.iter().filter(|s| s.borrow().is_some());
+        assert_eq!(cached.count(), 1);
+        // the first chain is loaded again when needed
+        assert_eq!(from_backup(&backup), expected);
+
+        let no_backend = BackupBuilder::<LocalBackend>::new().build();
+        assert_eq!(no_backend.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    // a toy encryption, flipping all the bits
+    struct NotDecryptor;
+
+    impl Decryptor for NotDecryptor {
+        fn decrypt(&self, input: &mut dyn Read) -> io::Result<Box<dyn Read>> {
+            let mut buf = Vec::new();
+            input.read_to_end(&mut buf)?;
+            let plain = buf.into_iter().map(|b| !b).collect::<Vec<_>>();
+            Ok(Box::new(io::Cursor::new(plain)))
+        }
+    }
+
+    // a backend keeping the files in memory
+    struct MemoryBackend(Vec<(String, Vec<u8>)>);
+
+    impl Backend for MemoryBackend {
+        type FileName = String;
+        type FileNameIter = Vec<String>;
+        type FileStream = io::Cursor<Vec<u8>>;
+
+        fn file_names(&self) -> io::Result<Self::FileNameIter> {
+            Ok(self.0.iter().map(|f| f.0.clone()).collect())
+        }
+
+        fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+            self.0
+                .iter()
+                .find(|f| Path::new(&f.0) == name)
+                .map(|f| io::Cursor::new(f.1.clone()))
+                .ok_or_else(|| not_found("file not available"))
+        }
+    }
+
+    // the single_vol backup, encrypted as duplicity does: not compressed and with a gpg extension
+    fn encrypted_single_vol() -> MemoryBackend {
+        let mut files = Vec::new();
+        for entry in fs::read_dir("tests/backups/single_vol").unwrap() {
+            let path = entry.unwrap().path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            let mut content = fs::read(&path).unwrap();
+            let name = match name.strip_suffix(".gz") {
+                Some(stem) => {
+                    let mut plain = Vec::new();
+                    flate2::read::GzDecoder::new(&content[..])
+                        .read_to_end(&mut plain)
+                        .unwrap();
+                    content = plain;
+                    stem.to_owned()
+                }
+                None => name,
+            };
+            let encrypted = content.into_iter().map(|b| !b).collect();
+            files.push((format!("{}.gpg", name), encrypted));
+        }
+        MemoryBackend(files)
+    }
+
+    #[test]
+    fn builder_decryptor() {
+        let plain = Backup::new(LocalBackend::new("tests/backups/single_vol")).unwrap();
+        let time = parse_time_str("20150617t182640z").unwrap();
+        fn read<B: Backend>(backup: &Backup<B>, time: Timespec) -> Vec<u8> {
+            let mut content = Vec::new();
+            let mut file = backup.open_file_at(b"executable", time).unwrap();
+            file.read_to_end(&mut content).unwrap();
+            content
+        }
+
+        // without a decryptor, the content of encrypted sets cannot be read
+        let backup = Backup::new(encrypted_single_vol()).unwrap();
+        let snapshot = backup.snapshots().unwrap().into_iter().next().unwrap();
+        assert!(snapshot.as_backup_set().is_encrypted());
+        assert!(!snapshot.can_read(b"executable").unwrap());
+
+        let backup = BackupBuilder::new()
+            .backend(encrypted_single_vol())
+            .decryptor(NotDecryptor)
+            .build()
+            .unwrap();
+        assert_eq!(from_backup(&backup), from_backup(&plain));
+        let snapshot = backup.snapshots().unwrap().into_iter().next().unwrap();
+        assert_eq!(snapshot.hostname().unwrap(), "dellxps");
+        assert!(snapshot.can_read(b"executable").unwrap());
+        assert_eq!(read(&backup, time), read(&plain, time));
+    }
+
     #[test]
     fn multi_chain_manifests() {
         let backend = LocalBackend::new("tests/backups/multi_chain");
@@ -599,4 +1932,138 @@ mod test {
             assert_eq!(e, *a);
         }
     }
+
+    #[test]
+    fn new_strict() {
+        let src = Path::new("tests/backups/single_vol");
+        let manifest = "duplicity-inc.20150617T182629Z.to.20150617T182650Z.manifest";
+        let dir = tempfile::tempdir().unwrap();
+        for entry in fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                fs::copy(&path, dir.path().join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        let partial = dir.path().join(format!("{}.part", manifest));
+        fs::copy(src.join(manifest), &partial).unwrap();
+
+        // the complete manifest is preferred
+        let backup = Backup::new_strict(LocalBackend::new(dir.path())).unwrap();
+        assert_eq!(backup.snapshots().unwrap().into_iter().count(), 3);
+
+        fs::remove_file(dir.path().join(manifest)).unwrap();
+        let backup = Backup::new(LocalBackend::new(dir.path())).unwrap();
+        let partials: Vec<_> = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.is_partial())
+            .collect();
+        assert_eq!(partials, vec![false, false, true]);
+        let err = Backup::new_strict(LocalBackend::new(dir.path())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn snapshot_sizes() {
+        let dir = Path::new("tests/backups/single_vol");
+        let volume_sizes: Vec<u64> = [
+            "duplicity-full.20150617T182545Z.vol1.difftar.gz",
+            "duplicity-inc.20150617T182545Z.to.20150617T182629Z.vol1.difftar.gz",
+            "duplicity-inc.20150617T182629Z.to.20150617T182650Z.vol1.difftar.gz",
+        ]
+        .iter()
+        .map(|name| fs::metadata(dir.join(name)).unwrap().len())
+        .collect();
+        let expected_cumulative: Vec<u64> = volume_sizes
+            .iter()
+            .scan(0, |sum, size| {
+                *sum += size;
+                Some(*sum)
+            })
+            .collect();
+
+        let local = Backup::new(LocalBackend::new(dir)).unwrap();
+        // a boxed backend provides no metadata, so the volumes are read
+        let boxed: Box<dyn backend::DynBackend> = Box::new(LocalBackend::new(dir));
+        let boxed = Backup::new(boxed).unwrap();
+        for snapshots in [local.snapshots().unwrap(), boxed.snapshots().unwrap()] {
+            let (incremental, cumulative): (Vec<_>, Vec<_>) = snapshots
+                .into_iter()
+                .map(|s| (s.incremental_size().unwrap(), s.cumulative_size().unwrap()))
+                .unzip();
+            assert_eq!(incremental, volume_sizes);
+            assert_eq!(cumulative, expected_cumulative);
+        }
+    }
+
+    #[test]
+    fn hostname_and_source_dir() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap();
+        for snapshot in backup.snapshots().unwrap() {
+            assert_eq!(snapshot.hostname().unwrap(), "dellxps");
+            assert_eq!(snapshot.source_dir().unwrap(), Some(PathBuf::from(".")));
+        }
+    }
+
+    #[test]
+    fn into_arc() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let backup = Backup::new(backend).unwrap().into_arc();
+        let shared = ArcBackup::clone(&backup);
+        let times: Vec<_> = backup
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.time())
+            .collect();
+        let shared_times: Vec<_> = shared
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.time())
+            .collect();
+        assert_eq!(times.len(), 4);
+        assert_eq!(times, shared_times);
+        assert_eq!(Arc::strong_count(&backup), 2);
+    }
+
+    #[test]
+    fn split_by_source() {
+        // mix the chains of different backups in the same directory
+        let dir = tempfile::tempdir().unwrap();
+        for src in &["single_vol", "tree", "multi_chain"] {
+            for entry in fs::read_dir(Path::new("tests/backups").join(src)).unwrap() {
+                let path = entry.unwrap().path();
+                if !path.is_file() {
+                    continue;
+                }
+                fs::copy(&path, dir.path().join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        // the last chain comes from a different host
+        let manifest = dir.path().join("duplicity-full.20160108T223209Z.manifest");
+        let content = fs::read_to_string(&manifest).unwrap();
+        fs::write(
+            &manifest,
+            content.replace("Hostname dellxps", "Hostname laptop"),
+        )
+        .unwrap();
+
+        let backup = Backup::new(LocalBackend::new(dir.path())).unwrap();
+        let views = backup.split_by_source().unwrap();
+        let actual: Vec<_> = views
+            .iter()
+            .map(|v| (v.hostname(), v.local_dir(), v.chain_ids().to_vec()))
+            .collect();
+        let expected = vec![
+            ("dellxps", Some(Path::new("dir1")), vec![0]),
+            ("dellxps", Some(Path::new(".")), vec![1, 2]),
+            ("laptop", Some(Path::new(".")), vec![3]),
+        ];
+        assert_eq!(actual, expected);
+        let counts: Vec<_> = views.iter().map(|v| v.snapshots().count()).collect();
+        assert_eq!(counts, vec![3, 4, 2]);
+    }
 }
@@ -3,12 +3,16 @@
 use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Read, Write};
 use std::num::ParseIntError;
 use std::path::Path;
 use std::str::{self, FromStr, Utf8Error};
 use std::usize;
 
+use sha1_smol::Sha1;
+
+use crate::backend::Backend;
+use crate::collections::{BackupChain, BackupSet};
 use crate::rawpath::RawPath;
 
 /// Manifest file info.
@@ -24,8 +28,47 @@ pub struct Manifest {
 pub struct Volume {
     start_path: PathBlock,
     end_path: PathBlock,
-    hash_type: String,
-    hash: Vec<u8>,
+    // all the hashes of the volume, at least one is present
+    hashes: Vec<(String, Vec<u8>)>,
+}
+
+/// The manifests of all the backup sets in a backup chain.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ManifestChain {
+    manifests: Vec<Manifest>,
+}
+
+/// The location of a path inside the volumes of a backup set.
+///
+/// Volumes are counted starting from one. The content of a path can be split across multiple
+/// volumes: in that case the blocks at the boundaries are reported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FileLocation {
+    /// The first volume containing the path.
+    pub first_volume: usize,
+    /// The last volume containing the path.
+    pub last_volume: usize,
+    /// The last block of the path stored in the first volume, if the path continues in the next
+    /// volume.
+    pub first_volume_end_block: Option<usize>,
+    /// The first block of the path stored in the last volume, if the path starts in a previous
+    /// volume.
+    pub last_volume_start_block: Option<usize>,
+}
+
+/// The result of the hash verification of a volume.
+///
+/// Each variant contains the number of the volume.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VolumeHashResult {
+    /// The hash of the volume file matches the one in the manifest.
+    Ok(usize),
+    /// The hash of the volume file does not match: the expected and the actual hashes are given.
+    Err(usize, Vec<u8>, Vec<u8>),
+    /// The volume file is not present in the backup set, or it cannot be read.
+    Missing(usize),
+    /// The hash type of the volume is not supported.
+    UnsupportedHash(usize),
 }
 
 /// Enumeration of parsing errors.
@@ -47,6 +90,8 @@ pub enum ParseError {
     ParseInt(ParseIntError),
     /// An error parsing an UTF-8 string.
     Utf8(Utf8Error),
+    /// An unrecognized keyword was found, while parsing in strict mode.
+    UnknownKeyword(String),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -58,17 +103,91 @@ struct PathBlock {
 struct ManifestParser<R> {
     input: R,
     buf: Vec<u8>,
+    strict: bool,
+    // the line in the buffer has been read in advance, and still needs to be processed
+    pending: bool,
 }
 
 struct WordIter<'a>(&'a [u8]);
 
 impl Manifest {
     /// Parses a stream to get a manifest.
+    ///
+    /// Lines starting with unknown keywords, that could be added by newer duplicity versions,
+    /// are ignored.
     pub fn parse<R: BufRead>(m: &mut R) -> Result<Self, ParseError> {
         let parser = ManifestParser::new(m);
         parser.parse()
     }
 
+    /// Parses a manifest from a string.
+    ///
+    /// This is a shortcut for `parse`, also available through the `FromStr` trait.
+    ///
+    /// # Examples
+    /// ```
+    /// use ruplicity::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_str(
+    ///     "Hostname dellxps\n\
+    ///      Localdir dir1\n\
+    ///      Volume 1:\n\
+    ///      \x20   StartingPath   .\n\
+    ///      \x20   EndingPath     file\n\
+    ///      \x20   Hash SHA1 e4a2e8e2abfba2cb24772e5ff9da4b85b3c19a0c\n",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(manifest.hostname(), "dellxps");
+    /// assert_eq!(manifest.volume(1).unwrap().end_path_bytes(), b"file");
+    ///
+    /// // the same, by using `str::parse`
+    /// let manifest: Manifest = "Hostname dellxps\nLocaldir dir1\n".parse().unwrap();
+    /// assert!(manifest.is_empty());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse(&mut io::Cursor::new(s.as_bytes()))
+    }
+
+    /// Parses a stream to get a manifest, rejecting unknown keywords.
+    ///
+    /// Unlike `parse`, this returns a `ParseError::UnknownKeyword` error whenever a line starts
+    /// with an unrecognized keyword.
+    pub fn parse_strict<R: BufRead>(m: &mut R) -> Result<Self, ParseError> {
+        let parser = ManifestParser::new_strict(m);
+        parser.parse()
+    }
+
+    /// Reads and parses a manifest file by using a backend.
+    ///
+    /// The file is read from scratch every time, so this can be used to pick up changes to a
+    /// manifest without opening the whole backup again.
+    pub fn reparse_from_backend<B: Backend>(backend: &B, path: &str) -> Result<Self, ParseError> {
+        let mut file = io::BufReader::new(backend.open_file(Path::new(path))?);
+        Self::parse(&mut file)
+    }
+
+    /// Writes the manifest in the duplicity format.
+    ///
+    /// Paths and names are quoted and escaped as duplicity does, so that parsing the result
+    /// gives back the same manifest.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"Hostname ")?;
+        w.write_all(escape_path(self.hostname.as_bytes()).as_bytes())?;
+        w.write_all(b"\nLocaldir ")?;
+        w.write_all(escape_path(self.local_dir.as_bytes()).as_bytes())?;
+        w.write_all(b"\n")?;
+        for (i, vol) in self.volumes.iter().enumerate() {
+            writeln!(w, "Volume {}:", i + 1)?;
+            write_path_block(w, "    StartingPath   ", &vol.start_path)?;
+            write_path_block(w, "    EndingPath     ", &vol.end_path)?;
+            for (htype, hash) in &vol.hashes {
+                writeln!(w, "    Hash {} {}", htype, to_hex(hash))?;
+            }
+        }
+        Ok(())
+    }
+
     /// The hostname produced the backup.
     pub fn hostname(&self) -> &str {
         &self.hostname
@@ -80,10 +199,24 @@ impl Manifest {
     }
 
     /// The index of the last volume.
+    ///
+    /// Since volumes are numbered starting from one, this is equal to `volume_count`.
     pub fn last_volume_index(&self) -> usize {
         self.volumes.len()
     }
 
+    /// The number of volumes.
+    pub fn volume_count(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Returns whether the manifest has no volumes.
+    ///
+    /// This could happen for an empty or interrupted backup.
+    pub fn is_empty(&self) -> bool {
+        self.volumes.is_empty()
+    }
+
     /// Returns the volume corresponding to the given index if present.
     ///
     /// Note that volumes are counting starting from one, so the last volume number is equal to the
@@ -144,6 +277,125 @@ impl Manifest {
             .map(|idx| idx + 1)
             .ok()
     }
+
+    /// Verifies the hashes of the volume files of the given backup set.
+    ///
+    /// The backup set must be the one described by this manifest. A result is returned for each
+    /// volume in the manifest, in order. The hash function is chosen from the hash type of the
+    /// volume: SHA1 and MD5 are supported. If a volume has multiple hashes, the first one with a
+    /// supported type is verified.
+    ///
+    /// The volumes are verified one at a time, even with the `parallel` feature enabled: use
+    /// `validate_volumes_hash_par` for a concurrent verification.
+    pub fn validate_volumes_hash<B: Backend>(
+        &self,
+        backend: &B,
+        backup_set: &BackupSet,
+    ) -> Vec<VolumeHashResult> {
+        (1..=self.volumes.len())
+            .map(|num| self.validate_volume_hash(backend, backup_set, num))
+            .collect()
+    }
+
+    /// Verifies the hashes of the volume files of the given backup set, in parallel.
+    ///
+    /// The results are the same as `validate_volumes_hash`, but the volumes are read and hashed
+    /// concurrently, so the backend must be `Sync`. Available with the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    pub fn validate_volumes_hash_par<B: Backend + Sync>(
+        &self,
+        backend: &B,
+        backup_set: &BackupSet,
+    ) -> Vec<VolumeHashResult> {
+        use rayon::prelude::*;
+
+        (1..=self.volumes.len())
+            .into_par_iter()
+            .map(|num| self.validate_volume_hash(backend, backup_set, num))
+            .collect()
+    }
+
+    fn validate_volume_hash<B: Backend>(
+        &self,
+        backend: &B,
+        backup_set: &BackupSet,
+        num: usize,
+    ) -> VolumeHashResult {
+        let (hash_type, expected) = match self.volumes[num - 1]
+            .hashes
+            .iter()
+            .find_map(|h| HashType::from_name(&h.0).map(|t| (t, &h.1)))
+        {
+            Some(hash) => hash,
+            None => return VolumeHashResult::UnsupportedHash(num),
+        };
+        let actual = backup_set
+            .volume_path(num)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "missing volume"))
+            .and_then(|path| backend.open_file(Path::new(path)))
+            .and_then(|file| hash_type.digest(file));
+        match actual {
+            Ok(ref hash) if hash == expected => VolumeHashResult::Ok(num),
+            Ok(hash) => VolumeHashResult::Err(num, expected.clone(), hash),
+            Err(_) => VolumeHashResult::Missing(num),
+        }
+    }
+
+    /// Returns the volumes containing the given path, if present.
+    ///
+    /// The given path is represented with a byte array, as in `first_volume_of_path`.
+    pub fn locate_path(&self, path: &[u8]) -> Option<FileLocation> {
+        let first_volume = self.first_volume_of_path(path)?;
+        let last_volume = self.last_volume_of_path(path)?;
+        let (first_volume_end_block, last_volume_start_block) = if first_volume < last_volume {
+            let first = &self.volumes[first_volume - 1];
+            let last = &self.volumes[last_volume - 1];
+            (
+                first.end_block().filter(|_| first.end_path_bytes() == path),
+                last.start_block()
+                    .filter(|_| last.start_path_bytes() == path),
+            )
+        } else {
+            (None, None)
+        };
+        Some(FileLocation {
+            first_volume,
+            last_volume,
+            first_volume_end_block,
+            last_volume_start_block,
+        })
+    }
+}
+
+impl ManifestChain {
+    /// Reads the manifests of all the backup sets in a chain by using a backend.
+    pub fn from_backend<B: Backend>(backend: &B, chain: &BackupChain) -> Result<Self, ParseError> {
+        let mut result = ManifestChain::default();
+        result.reload(backend, chain)?;
+        Ok(result)
+    }
+
+    /// Reads again the manifests of all the backup sets in a chain, replacing the current ones.
+    ///
+    /// If one of the manifests cannot be read, the error is returned and the current manifests
+    /// are left untouched.
+    pub fn reload<B: Backend>(
+        &mut self,
+        backend: &B,
+        chain: &BackupChain,
+    ) -> Result<(), ParseError> {
+        let manifests = (0..chain.num_sets())
+            .filter_map(|i| chain.nth_set(i))
+            .map(|set| Manifest::reparse_from_backend(backend, set.manifest_path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.manifests = manifests;
+        Ok(())
+    }
+
+    /// Returns the manifests, starting from the one of the full set.
+    pub fn manifests(&self) -> &[Manifest] {
+        &self.manifests
+    }
 }
 
 impl Volume {
@@ -192,13 +444,37 @@ impl Volume {
     }
 
     /// Returns a string representing the hash type of this volume.
+    ///
+    /// If the volume has multiple hashes, the type of the first one is returned.
     pub fn hash_type(&self) -> &str {
-        &self.hash_type
+        &self.hashes[0].0
     }
 
     /// Returns the hash value of this volume.
+    ///
+    /// If the volume has multiple hashes, the first one is returned.
     pub fn hash(&self) -> &[u8] {
-        &self.hash
+        &self.hashes[0].1
+    }
+
+    /// Returns the hash value of this volume, as a lowercase hex string.
+    pub fn hash_hex(&self) -> String {
+        to_hex(self.hash())
+    }
+
+    /// Returns all the hashes of this volume, as pairs of hash type and value.
+    ///
+    /// Duplicity could store hashes computed with different algorithms for the same volume.
+    pub fn hashes(&self) -> &[(String, Vec<u8>)] {
+        &self.hashes
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Manifest::from_str(s)
     }
 }
 
@@ -217,6 +493,7 @@ impl Display for ParseError {
             }
             ParseError::ParseInt(ref e) => write!(fmt, "{}", e),
             ParseError::Utf8(ref e) => write!(fmt, "{}", e),
+            ParseError::UnknownKeyword(ref e) => write!(fmt, "unknown keyword '{}' in manifest", e),
         }
     }
 }
@@ -239,6 +516,15 @@ impl From<Utf8Error> for ParseError {
     }
 }
 
+const KEYWORDS: &[&str] = &[
+    "Hostname",
+    "Localdir",
+    "Volume",
+    "StartingPath",
+    "EndingPath",
+    "Hash",
+];
+
 macro_rules! check_eof(
     ($e:expr) => (
         if !$e? {
@@ -253,13 +539,22 @@ impl<R: BufRead> ManifestParser<R> {
         ManifestParser {
             input: input,
             buf: vec![],
+            strict: false,
+            pending: false,
+        }
+    }
+
+    pub fn new_strict(input: R) -> Self {
+        ManifestParser {
+            strict: true,
+            ..Self::new(input)
         }
     }
 
     pub fn parse(mut self) -> Result<Manifest, ParseError> {
-        check_eof!(self.read_line());
+        check_eof!(self.read_keyword_line());
         let hostname = self.read_param_str("Hostname")?;
-        check_eof!(self.read_line());
+        check_eof!(self.read_keyword_line());
         let local_dir = RawPath::from_bytes(self.read_param_bytes("Localdir")?);
 
         let mut volumes = Vec::new();
@@ -279,7 +574,7 @@ impl<R: BufRead> ManifestParser<R> {
     }
 
     fn read_volume(&mut self) -> Result<Option<(Volume, usize)>, ParseError> {
-        if !self.read_line()? {
+        if !self.read_keyword_line()? {
             // EOF
             return Ok(None);
         }
@@ -290,22 +585,50 @@ impl<R: BufRead> ManifestParser<R> {
             param.pop();
         }
         let num = usize::from_str(&param)?;
-        check_eof!(self.read_line());
+        check_eof!(self.read_keyword_line());
         let start_path = self.read_path_block("StartingPath")?;
-        check_eof!(self.read_line());
+        check_eof!(self.read_keyword_line());
         let end_path = self.read_path_block("EndingPath")?;
-        check_eof!(self.read_line());
-        let (htype, h) = self.read_hash_param()?;
+        check_eof!(self.read_keyword_line());
+        let mut hashes = vec![self.read_hash_param()?];
+        // additional hashes, with different algorithms, could follow
+        while self.read_keyword_line()? {
+            if WordIter(&self.buf).next() != Some(b"Hash") {
+                self.pending = true;
+                break;
+            }
+            hashes.push(self.read_hash_param()?);
+        }
 
         let vol = Volume {
             start_path: start_path,
             end_path: end_path,
-            hash_type: htype,
-            hash: h,
+            hashes,
         };
         Ok(Some((vol, num)))
     }
 
+    // reads the next line starting with a known keyword, by skipping the others, or failing in
+    // strict mode
+    fn read_keyword_line(&mut self) -> Result<bool, ParseError> {
+        if self.pending {
+            self.pending = false;
+            return Ok(true);
+        }
+        while self.read_line()? {
+            let kw = WordIter(&self.buf).next().unwrap_or(b"");
+            if KEYWORDS.iter().any(|k| k.as_bytes() == kw) {
+                return Ok(true);
+            }
+            if self.strict {
+                return Err(ParseError::UnknownKeyword(
+                    String::from_utf8_lossy(kw).into_owned(),
+                ));
+            }
+        }
+        Ok(false)
+    }
+
     fn read_line(&mut self) -> io::Result<bool> {
         self.buf.clear();
         let mut len = self.input.read_until(b'\n', &mut self.buf)?;
@@ -335,7 +658,7 @@ impl<R: BufRead> ManifestParser<R> {
                 return Ok(vec![]);
             }
         };
-        Ok(unescape(param))
+        Ok(unescape_path(param))
     }
 
     fn read_param_str(&mut self, key: &str) -> Result<String, ParseError> {
@@ -353,7 +676,7 @@ impl<R: BufRead> ManifestParser<R> {
             return Err(ParseError::MissingKeyword(key.to_owned()));
         }
         let path = match words.next() {
-            Some(word) => RawPath::from_bytes(unescape(word)),
+            Some(word) => RawPath::from_bytes(unescape_path(word)),
             None => {
                 return Err(ParseError::MissingPath);
             }
@@ -425,7 +748,63 @@ impl<'a> Iterator for WordIter<'a> {
     }
 }
 
-fn unescape(mut buf: &[u8]) -> Vec<u8> {
+/// Escapes a path as duplicity does in manifests and logs.
+///
+/// Spaces, quotes, backslashes and non-printable bytes are replaced by `\xNN` sequences, and the
+/// result is quoted if anything has been escaped. Unlike duplicity, bytes that are not valid
+/// UTF-8 are escaped as well, so the result is always a valid string. `unescape_path` gives
+/// back the original bytes.
+///
+/// # Examples
+/// ```
+/// use ruplicity::manifest::{escape_path, unescape_path};
+///
+/// assert_eq!(escape_path(b"dir/file"), "dir/file");
+/// assert_eq!(escape_path(b"my file\xff"), "\"my\\x20file\\xff\"");
+/// assert_eq!(unescape_path(escape_path(b"my file\xff").as_bytes()), b"my file\xff");
+/// ```
+pub fn escape_path(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() + 2);
+    let mut escaped = bytes.is_empty();
+    let mut push_escaped = |result: &mut String, b: u8| {
+        result.push_str(&format!("\\x{:02x}", b));
+        escaped = true;
+    };
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (valid, invalid) = match str::from_utf8(rest) {
+            Ok(s) => (s, &rest[rest.len()..]),
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                let invalid_len = e.error_len().unwrap_or(after.len());
+                // the prefix has been already validated
+                (str::from_utf8(valid).unwrap(), &after[..invalid_len])
+            }
+        };
+        for c in valid.chars() {
+            if c.is_ascii() && needs_escape(c as u8) {
+                push_escaped(&mut result, c as u8);
+            } else {
+                result.push(c);
+            }
+        }
+        for &b in invalid {
+            push_escaped(&mut result, b);
+        }
+        rest = &rest[valid.len() + invalid.len()..];
+    }
+    if escaped {
+        result.insert(0, '"');
+        result.push('"');
+    }
+    result
+}
+
+/// Unescapes a path from a duplicity manifest.
+///
+/// Surrounding quotes are removed, and `\xNN` sequences are replaced by the corresponding bytes.
+/// This is the inverse of `escape_path`.
+pub fn unescape_path(mut buf: &[u8]) -> Vec<u8> {
     let mut result = Vec::with_capacity(buf.len());
     buf = match (buf.first().cloned(), buf.last().cloned()) {
         // quoted
@@ -459,6 +838,19 @@ fn unescape(mut buf: &[u8]) -> Vec<u8> {
     result
 }
 
+fn needs_escape(b: u8) -> bool {
+    b == b'"' || b == b'\\' || b == b'\'' || b <= b' ' || b == 0x7f
+}
+
+fn write_path_block<W: Write>(w: &mut W, key: &str, pb: &PathBlock) -> io::Result<()> {
+    w.write_all(key.as_bytes())?;
+    w.write_all(escape_path(pb.path.as_bytes()).as_bytes())?;
+    if let Some(block) = pb.block {
+        write!(w, " {}", block)?;
+    }
+    w.write_all(b"\n")
+}
+
 fn from_hex(s: &[u8]) -> Vec<u8> {
     let mut res = Vec::with_capacity(s.len() / 2);
     let mut buf: u8 = 0;
@@ -475,6 +867,63 @@ fn from_hex(s: &[u8]) -> Vec<u8> {
     res
 }
 
+// the hash functions supported for volume verification
+#[derive(Copy, Clone, Debug)]
+enum HashType {
+    Sha1,
+    Md5,
+}
+
+impl HashType {
+    fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("SHA1") {
+            Some(HashType::Sha1)
+        } else if name.eq_ignore_ascii_case("MD5") {
+            Some(HashType::Md5)
+        } else {
+            None
+        }
+    }
+
+    fn digest<R: Read>(self, input: R) -> io::Result<Vec<u8>> {
+        match self {
+            HashType::Sha1 => {
+                let mut hasher = Sha1::new();
+                read_all(input, |buf| hasher.update(buf))?;
+                Ok(hasher.digest().bytes().to_vec())
+            }
+            HashType::Md5 => {
+                let mut context = md5::Context::new();
+                read_all(input, |buf| context.consume(buf))?;
+                Ok(context.compute().0.to_vec())
+            }
+        }
+    }
+}
+
+fn read_all<R: Read, F: FnMut(&[u8])>(mut input: R, mut f: F) -> io::Result<()> {
+    let mut buf = [0; 8192];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut res = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        res.push(DIGITS[(b >> 4) as usize] as char);
+        res.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    res
+}
+
 fn nibble(b: u8) -> u8 {
     match b {
         b'a'..=b'f' => b - b'a' + 10,
@@ -486,8 +935,11 @@ fn nibble(b: u8) -> u8 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::backend::local::LocalBackend;
+    use crate::collections::Collections;
     use std::fs::File;
     use std::io::BufReader;
+    use std::iter;
     use std::path::Path;
 
     fn full1_manifest() -> Result<Manifest, ParseError> {
@@ -502,6 +954,55 @@ mod test {
         Manifest::parse(&mut bfile)
     }
 
+    #[test]
+    fn reparse_from_backend() {
+        let backend = LocalBackend::new("tests/manifest");
+        let manifest = Manifest::reparse_from_backend(&backend, "full1.manifest").unwrap();
+        assert_eq!(manifest, full1_manifest().unwrap());
+        assert!(Manifest::reparse_from_backend(&backend, "missing.manifest").is_err());
+    }
+
+    #[test]
+    fn manifest_chain_reload() {
+        let backend = LocalBackend::new("tests/backups/multi_chain");
+        let coll = Collections::from_filenames(backend.file_names().unwrap());
+        let mut chains = coll.backup_chains();
+        let (first, second) = (chains.next().unwrap(), chains.next().unwrap());
+        let read = |chain: &BackupChain| {
+            (0..chain.num_sets())
+                .map(|i| {
+                    let path = chain.nth_set(i).unwrap().manifest_path();
+                    Manifest::reparse_from_backend(&backend, path).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut manifests = ManifestChain::from_backend(&backend, first).unwrap();
+        assert_eq!(manifests.manifests().len(), 2);
+        assert_eq!(manifests.manifests(), &read(first)[..]);
+        manifests.reload(&backend, second).unwrap();
+        assert_eq!(manifests.manifests(), &read(second)[..]);
+
+        // a failed reload keeps the previous manifests
+        let empty = LocalBackend::new("tests/manifest");
+        assert!(manifests.reload(&empty, first).is_err());
+        assert_eq!(manifests.manifests(), &read(second)[..]);
+    }
+
+    #[test]
+    fn empty() {
+        let file = File::open("tests/manifest/empty.manifest").unwrap();
+        let manifest = Manifest::parse(&mut BufReader::new(file)).unwrap();
+        assert_eq!(manifest.hostname(), "dellxps");
+        assert_eq!(manifest.volume_count(), 0);
+        assert!(manifest.is_empty());
+        assert!(manifest.volume(1).is_none());
+
+        let manifest = full1_manifest().unwrap();
+        assert_eq!(manifest.volume_count(), 1);
+        assert!(!manifest.is_empty());
+    }
+
     #[test]
     fn parse_no_err_full() {
         full1_manifest().unwrap();
@@ -576,6 +1077,222 @@ mod test {
         );
     }
 
+    #[test]
+    fn locate_path() {
+        let manifest = inc1_manifest().unwrap();
+        assert_eq!(
+            manifest.locate_path(
+                b"home/michele/Documenti/Scuola/Open Class/\
+                  Epfl/Principles of Reactive Programming/lectures/\
+                  week7/lecture_slides_week7-1-annotated.pdf"
+            ),
+            Some(FileLocation {
+                first_volume: 1,
+                last_volume: 2,
+                first_volume_end_block: Some(59),
+                last_volume_start_block: Some(60),
+            })
+        );
+        assert_eq!(
+            manifest.locate_path(b"home/michele/Immagini/Foto/albumfiles.txt"),
+            Some(FileLocation {
+                first_volume: 28,
+                last_volume: 28,
+                first_volume_end_block: None,
+                last_volume_start_block: None,
+            })
+        );
+        assert_eq!(
+            manifest.locate_path(b"home/michele/Immagini/Foto/foto1.jpg"),
+            None
+        );
+    }
+
+    // a backend altering the content of the given file
+    struct TamperBackend {
+        inner: LocalBackend,
+        tampered: &'static str,
+    }
+
+    impl Backend for TamperBackend {
+        type FileName = <LocalBackend as Backend>::FileName;
+        type FileNameIter = <LocalBackend as Backend>::FileNameIter;
+        type FileStream = io::Cursor<Vec<u8>>;
+
+        fn file_names(&self) -> io::Result<Self::FileNameIter> {
+            self.inner.file_names()
+        }
+
+        fn open_file(&self, name: &Path) -> io::Result<Self::FileStream> {
+            let mut content = Vec::new();
+            self.inner.open_file(name)?.read_to_end(&mut content)?;
+            if name == Path::new(self.tampered) {
+                content[0] ^= 0xff;
+            }
+            Ok(io::Cursor::new(content))
+        }
+    }
+
+    fn single_vol_hash_results(tampered: &'static str) -> Vec<Vec<VolumeHashResult>> {
+        let backend = TamperBackend {
+            inner: LocalBackend::new("tests/backups/single_vol"),
+            tampered,
+        };
+        let collections = Collections::from_filenames(backend.file_names().unwrap());
+        let chain = collections.backup_chains().next().unwrap();
+        iter::once(chain.full_set())
+            .chain(chain.inc_sets())
+            .map(|set| {
+                let manifest = Manifest::reparse_from_backend(&backend, set.manifest_path());
+                manifest.unwrap().validate_volumes_hash(&backend, set)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validate_volumes_hash() {
+        let results = single_vol_hash_results("");
+        let expected = vec![vec![VolumeHashResult::Ok(1)]; 3];
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn validate_volumes_hash_tampered() {
+        let results = single_vol_hash_results(
+            "duplicity-inc.20150617T182545Z.to.20150617T182629Z.vol1.difftar.gz",
+        );
+        assert_eq!(results[0], vec![VolumeHashResult::Ok(1)]);
+        assert_eq!(results[2], vec![VolumeHashResult::Ok(1)]);
+        match results[1][..] {
+            [VolumeHashResult::Err(1, ref expected, ref actual)] => {
+                assert_eq!(to_hex(expected), "8f0b68e072cd54cdae5baade2504d8e6028be678");
+                assert_ne!(expected, actual);
+            }
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn validate_volumes_hash_par() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let collections = Collections::from_filenames(backend.file_names().unwrap());
+        let chain = collections.backup_chains().next().unwrap();
+        for set in iter::once(chain.full_set()).chain(chain.inc_sets()) {
+            let manifest = Manifest::reparse_from_backend(&backend, set.manifest_path()).unwrap();
+            assert_eq!(
+                manifest.validate_volumes_hash_par(&backend, set),
+                manifest.validate_volumes_hash(&backend, set)
+            );
+        }
+    }
+
+    #[test]
+    fn validate_volumes_hash_missing() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let manifest_path = "duplicity-full.20150617T182545Z.manifest";
+        let manifest = Manifest::reparse_from_backend(&backend, manifest_path).unwrap();
+        // the collection does not contain the volume file
+        let collections = Collections::from_filenames(&[manifest_path]);
+        let set = collections.backup_chains().next().unwrap().full_set();
+        assert_eq!(
+            manifest.validate_volumes_hash(&backend, set),
+            vec![VolumeHashResult::Missing(1)]
+        );
+    }
+
+    #[test]
+    fn validate_volumes_hash_types() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let manifest_path = "duplicity-full.20150617T182545Z.manifest";
+        let collections = Collections::from_filenames(backend.file_names().unwrap());
+        let set = collections.backup_chains().next().unwrap().full_set();
+        let mut content = Vec::new();
+        backend
+            .open_file(Path::new(manifest_path))
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        let content = String::from_utf8_lossy(&content).into_owned();
+        let sha1 = "Hash SHA1 e4a2e8e2abfba2cb24772e5ff9da4b85b3c19a0c";
+        let results = |hash: &str| {
+            let manifest = Manifest::from_str(&content.replace(sha1, hash)).unwrap();
+            manifest.validate_volumes_hash(&backend, set)
+        };
+
+        assert_eq!(
+            results("Hash MD5 1b7835145e85f3155b400443be771bc0"),
+            vec![VolumeHashResult::Ok(1)]
+        );
+        let expected = from_hex(b"00000000000000000000000000000000");
+        assert_eq!(
+            results("Hash md5 00000000000000000000000000000000"),
+            vec![VolumeHashResult::Err(
+                1,
+                expected,
+                from_hex(b"1b7835145e85f3155b400443be771bc0")
+            )]
+        );
+        assert_eq!(
+            results("Hash SHA256 00"),
+            vec![VolumeHashResult::UnsupportedHash(1)]
+        );
+    }
+
+    #[test]
+    fn multiple_hashes() {
+        let file = File::open("tests/manifest/multi_hash.manifest").unwrap();
+        let manifest = Manifest::parse(&mut BufReader::new(file)).unwrap();
+        assert_eq!(manifest.volume_count(), 2);
+
+        let vol = manifest.volume(1).unwrap();
+        assert_eq!(vol.hash_type(), "MD5");
+        assert_eq!(vol.hash_hex(), "5d41402abc4b2a76b9719d911017c592");
+        let hashes = vol
+            .hashes()
+            .iter()
+            .map(|h| (h.0.as_str(), to_hex(&h.1)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            vec![
+                ("MD5", "5d41402abc4b2a76b9719d911017c592".to_owned()),
+                (
+                    "SHA1",
+                    "e4a2e8e2abfba2cb24772e5ff9da4b85b3c19a0c".to_owned()
+                ),
+            ]
+        );
+
+        let vol = manifest.volume(2).unwrap();
+        assert_eq!(vol.start_path_bytes(), b"file");
+        assert_eq!(vol.start_block(), Some(4));
+        assert_eq!(vol.hash_type(), "SHA1");
+        assert_eq!(vol.hashes().len(), 2);
+
+        // single hashes are still parsed as before
+        let manifest = full1_manifest().unwrap();
+        assert_eq!(manifest.volume(1).unwrap().hashes().len(), 1);
+    }
+
+    #[test]
+    fn from_str() {
+        let mut content = String::new();
+        File::open("tests/manifest/inc1.manifest")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(
+            Manifest::from_str(&content).unwrap(),
+            inc1_manifest().unwrap()
+        );
+        assert_eq!(
+            content.parse::<Manifest>().unwrap(),
+            inc1_manifest().unwrap()
+        );
+        assert!(Manifest::from_str("Hostname dellxps\n").is_err());
+    }
+
     #[test]
     fn full1_data() {
         let manifest = full1_manifest().unwrap();
@@ -602,5 +1319,80 @@ mod test {
             0x4b, 0x85, 0xb3, 0xc1, 0x9a, 0x0c,
         ];
         assert_eq!(vol.hash().to_vec(), hash);
+        assert_eq!(vol.hash_hex(), "e4a2e8e2abfba2cb24772e5ff9da4b85b3c19a0c");
+    }
+
+    #[test]
+    fn unknown_keywords() {
+        let open = || BufReader::new(File::open("tests/manifest/extra_keywords.manifest").unwrap());
+        let manifest = Manifest::parse(&mut open()).unwrap();
+        let expected = inc1_manifest().unwrap();
+        assert_eq!(manifest.hostname(), expected.hostname());
+        assert_eq!(manifest.local_dir(), expected.local_dir());
+        assert_eq!(manifest.volume_count(), 2);
+        assert_eq!(manifest.volume(1), expected.volume(1));
+        assert_eq!(manifest.volume(2), expected.volume(2));
+
+        match Manifest::parse_strict(&mut open()) {
+            Err(ParseError::UnknownKeyword(kw)) => assert_eq!(kw, "Encrypted"),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn parse_strict_known_keywords() {
+        let file = File::open("tests/manifest/inc1.manifest").unwrap();
+        let manifest = Manifest::parse_strict(&mut BufReader::new(file)).unwrap();
+        assert_eq!(manifest, inc1_manifest().unwrap());
+    }
+
+    #[test]
+    fn escape_path_round_trip() {
+        let cases: Vec<(&[u8], &str)> = vec![
+            (b"", "\"\""),
+            (b"dir/file", "dir/file"),
+            (b"with space", "\"with\\x20space\""),
+            (b"\xd8\xab\xb1W", "\"\u{62b}\\xb1W\""),
+            ("caf\u{e9} \"".as_bytes(), "\"caf\u{e9}\\x20\\x22\""),
+            (b"nl\n\xff end", "\"nl\\x0a\\xff\\x20end\""),
+            (b"quotes\"'\\", "\"quotes\\x22\\x27\\x5c\""),
+            (b"\t~`", "\"\\x09~`\""),
+        ];
+        for (bytes, escaped) in cases {
+            assert_eq!(escape_path(bytes), escaped);
+            assert_eq!(unescape_path(escape_path(bytes).as_bytes()), bytes);
+        }
+    }
+
+    #[test]
+    fn write_round_trip() {
+        for path in &[
+            "tests/manifest/full1.manifest",
+            "tests/manifest/inc1.manifest",
+            "tests/manifest/multi_hash.manifest",
+        ] {
+            let mut file = BufReader::new(File::open(path).unwrap());
+            let manifest = Manifest::parse(&mut file).unwrap();
+            let mut buf = Vec::new();
+            manifest.write(&mut buf).unwrap();
+            let reparsed = Manifest::parse_strict(&mut &buf[..]).unwrap();
+            assert_eq!(reparsed, manifest, "manifest {}", path);
+        }
+
+        // paths needing escapes, including bytes that are not valid UTF-8
+        let mut file = BufReader::new(File::open("tests/manifest/full1.manifest").unwrap());
+        let mut manifest = Manifest::parse(&mut file).unwrap();
+        manifest.volumes[0].start_path.path = RawPath::from_bytes(b"dir/a \"b\xff".to_vec());
+        let mut buf = Vec::new();
+        manifest.write(&mut buf).unwrap();
+        let expected = b"StartingPath   \"dir/a\\x20\\x22b\\xff\"";
+        assert!(buf.windows(expected.len()).any(|w| w == &expected[..]));
+        assert_eq!(Manifest::parse_strict(&mut &buf[..]).unwrap(), manifest);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let hex = "00ff10a9e4";
+        assert_eq!(to_hex(&from_hex(hex.as_bytes())), hex);
     }
 }
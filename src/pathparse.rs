@@ -0,0 +1,129 @@
+//! Parsing of the paths stored in duplicity archives.
+//!
+//! Duplicity stores each file in signature and volume archives under a prefix, determining how
+//! the content is stored, e.g. `snapshot/dir/file` or `deleted/dir/file`. Files split in multiple
+//! blocks are stored under a `multivol` prefix, with the block number as the last component, e.g.
+//! `multivol_snapshot/dir/file/3`.
+//!
+//! # Example
+//!
+//! ```
+//! use ruplicity::pathparse::{self, PrefixKind};
+//!
+//! let parsed = pathparse::parse(b"multivol_diff/dir/file/2");
+//! assert_eq!(parsed, Some((PrefixKind::MultivolDiff, &b"dir/file"[..], Some(2))));
+//! ```
+
+use std::str;
+
+/// The prefix of a path in a duplicity archive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PrefixKind {
+    /// The signature of a file, stored in signature archives.
+    Signature,
+    /// The full content of a file.
+    Snapshot,
+    /// A file deleted since the previous snapshot.
+    Deleted,
+    /// The difference of a file from the previous snapshot.
+    Diff,
+    /// A block of the full content of a file.
+    MultivolSnapshot,
+    /// A block of the difference of a file from the previous snapshot.
+    MultivolDiff,
+}
+
+impl PrefixKind {
+    /// Returns whether the path is split in multiple blocks.
+    pub fn is_multivol(self) -> bool {
+        self == PrefixKind::MultivolSnapshot || self == PrefixKind::MultivolDiff
+    }
+}
+
+/// A parsed path: the prefix, the real path and the optional block number.
+pub type ParsedPath<'a> = (PrefixKind, &'a [u8], Option<usize>);
+
+/// Splits a path from a duplicity archive in its prefix, the real path and the block number.
+///
+/// The real path of the root directory is empty, and a trailing slash for directories is
+/// removed. The block number is present only for `multivol` prefixes. Returns `None` if the
+/// prefix is not recognized, or the block number is missing.
+pub fn parse(path: &[u8]) -> Option<ParsedPath<'_>> {
+    // split the path in (first directory, the remaining path)
+    // the first is the type, the remaining is the real path
+    let pos = path.iter().cloned().position(|b| b == b'/')?;
+    let (pfirst, raw_real) = path.split_at(pos);
+    let kind = match pfirst {
+        b"signature" => PrefixKind::Signature,
+        b"snapshot" => PrefixKind::Snapshot,
+        b"deleted" => PrefixKind::Deleted,
+        b"diff" => PrefixKind::Diff,
+        b"multivol_snapshot" => PrefixKind::MultivolSnapshot,
+        b"multivol_diff" => PrefixKind::MultivolDiff,
+        _ => {
+            return None;
+        }
+    };
+    let real = match raw_real.last().cloned() {
+        Some(b'/') if raw_real.len() > 1 => &raw_real[1..raw_real.len() - 1],
+        _ => &raw_real[1..],
+    };
+    if !kind.is_multivol() {
+        return Some((kind, real, None));
+    }
+    // the last component is the block number
+    let pos = real.iter().cloned().rposition(|b| b == b'/');
+    let (real, block) = match pos {
+        Some(pos) => (&real[..pos], &real[pos + 1..]),
+        None => (&real[..0], real),
+    };
+    let block = str::from_utf8(block).ok()?.parse().ok()?;
+    Some((kind, real, Some(block)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_prefixes() {
+        let cases: Vec<(&[u8], Option<ParsedPath<'_>>)> = vec![
+            (
+                b"signature/file",
+                Some((PrefixKind::Signature, b"file", None)),
+            ),
+            (
+                b"snapshot/dir/file",
+                Some((PrefixKind::Snapshot, b"dir/file", None)),
+            ),
+            (b"snapshot/dir/", Some((PrefixKind::Snapshot, b"dir", None))),
+            (b"snapshot/", Some((PrefixKind::Snapshot, b"", None))),
+            (b"deleted/file", Some((PrefixKind::Deleted, b"file", None))),
+            (b"diff/a/b/c", Some((PrefixKind::Diff, b"a/b/c", None))),
+            (
+                b"multivol_snapshot/dir/file/3",
+                Some((PrefixKind::MultivolSnapshot, b"dir/file", Some(3))),
+            ),
+            (
+                b"multivol_snapshot/file/0",
+                Some((PrefixKind::MultivolSnapshot, b"file", Some(0))),
+            ),
+            (
+                b"multivol_diff/dir/file/12",
+                Some((PrefixKind::MultivolDiff, b"dir/file", Some(12))),
+            ),
+            (
+                b"multivol_diff/dir\xff/file/1",
+                Some((PrefixKind::MultivolDiff, b"dir\xff/file", Some(1))),
+            ),
+            (b"multivol_diff/dir/file", None),
+            (b"multivol_snapshot/file/x", None),
+            (b"unknown/file", None),
+            (b"snapshot", None),
+            (b"", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(parse(path), expected, "path {:?}", path);
+        }
+    }
+}
@@ -7,16 +7,20 @@ use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 use std::io::{self, Read};
 use std::iter::Iterator;
-use std::path::Path;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
 use std::slice;
 
-use flate2::read::GzDecoder;
 use tar;
 use time::Timespec;
 
 use crate::backend::Backend;
 use crate::collections::{SignatureChain, SignatureFile};
+use crate::compression::Compression;
+use crate::pathparse::{self, PrefixKind};
 use crate::rawpath::RawPath;
+#[cfg(feature = "serde")]
+use crate::timefmt::SortableTimeDisplay;
 use crate::timefmt::TimeDisplay;
 
 /// Stores information about paths in a backup chain.
@@ -25,6 +29,7 @@ use crate::timefmt::TimeDisplay;
 #[derive(Debug)]
 pub struct Chain {
     num_snapshots: u8,
+    snapshot_times: Vec<Timespec>,
     files: Vec<PathSnapshots>,
     ug_map: UserGroupMap,
 }
@@ -64,6 +69,127 @@ pub struct Entry<'a> {
     path: &'a RawPath,
     info: &'a PathInfo,
     ug_map: &'a UserGroupMap,
+    // whether the info comes from the snapshot of the entry, instead of a previous one
+    changed: bool,
+}
+
+/// The changes in the entries between two snapshots of a chain.
+///
+/// This is an iterator over all the paths present in at least one of the two snapshots, sorted
+/// by path.
+#[derive(Clone)]
+pub struct SnapshotDiff<'a> {
+    from: u8,
+    to: u8,
+    iter: slice::Iter<'a, PathSnapshots>,
+    chain: &'a Chain,
+}
+
+/// The change of an entry between two snapshots.
+#[derive(Debug)]
+pub struct DiffEntry<'a> {
+    /// The kind of change.
+    pub kind: DiffKind,
+    /// The entry in the first snapshot, if present.
+    pub old: Option<Entry<'a>>,
+    /// The entry in the second snapshot, if present.
+    pub new: Option<Entry<'a>>,
+}
+
+/// The kind of change of an entry between two snapshots.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DiffKind {
+    /// The entry is present only in the second snapshot.
+    Added,
+    /// The entry is present only in the first snapshot.
+    Removed,
+    /// The entry is present in both the snapshots, with different metadata.
+    Modified,
+    /// The entry is present in both the snapshots, with the same metadata.
+    Unchanged,
+}
+
+/// A key identifying an entry, ordered by path.
+///
+/// Entries with the same key represent the same path in different snapshots.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct EntryKey<'a>(&'a [u8]);
+
+/// Ownership information of an entry.
+///
+/// Contains the numeric ids of the owner user and group, and their names, if available.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Ownership<'a> {
+    /// The owner's user ID.
+    pub uid: Option<u64>,
+    /// The owner's group ID.
+    pub gid: Option<u64>,
+    /// The name of the owner user.
+    pub uname: Option<&'a str>,
+    /// The name of the owner group.
+    pub gname: Option<&'a str>,
+}
+
+/// The file system metadata of an entry, as needed to restore it.
+///
+/// Owner information is available only on unix platforms.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FileStat {
+    /// The owner's user ID.
+    #[cfg(unix)]
+    pub uid: Option<u64>,
+    /// The owner's group ID.
+    #[cfg(unix)]
+    pub gid: Option<u64>,
+    /// The mode bits, including the file type.
+    pub mode: Option<u32>,
+    /// The seconds of the last modification time, since the Unix epoch.
+    pub mtime_sec: i64,
+    /// The nanoseconds of the last modification time.
+    pub mtime_nsec: i32,
+}
+
+/// A summary of the entries in a snapshot, and of the changes from the previous snapshot.
+///
+/// A list of summaries for all the snapshots in a chain can be obtained by converting the chain
+/// into a `Vec<SnapshotSummary>`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SnapshotSummary {
+    /// The time of the snapshot.
+    ///
+    /// When serialized, the time is represented as seconds since the Unix epoch.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::timefmt::serialize_timespec")
+    )]
+    pub time: Timespec,
+    /// The number of entries present in the snapshot.
+    pub file_count: usize,
+    /// The sum of the upper bounds of the entry sizes, see `Entry::size_hint`.
+    pub total_size_hint_bytes: u64,
+    /// The number of entries added since the previous snapshot.
+    pub num_added: usize,
+    /// The number of entries deleted since the previous snapshot.
+    pub num_deleted: usize,
+    /// The number of entries modified since the previous snapshot.
+    pub num_modified: usize,
+}
+
+// the representation of an entry in `SnapshotEntries::to_json`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonEntry<'a> {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_hex: Option<String>,
+    #[serde(rename = "type")]
+    entry_type: &'static str,
+    size: Option<usize>,
+    mtime: String,
+    mode: Option<u32>,
+    owner: Option<&'a str>,
+    group: Option<&'a str>,
 }
 
 /// Type of entry in a backup snapshot.
@@ -85,13 +211,6 @@ pub enum EntryType {
     Unknown(u8),
 }
 
-#[derive(Copy, Clone, Debug)]
-enum DiffType {
-    Signature,
-    Snapshot,
-    Deleted,
-}
-
 #[derive(Debug)]
 struct PathSnapshots {
     // the directory or file path
@@ -117,6 +236,7 @@ struct PathInfo {
     entry_type: u8,
     size_hint: Option<(usize, usize)>,
     link: Option<RawPath>,
+    device: Option<(u32, u32)>,
 }
 
 #[derive(Debug)]
@@ -128,11 +248,18 @@ struct UserGroupMap {
 #[derive(Debug)]
 struct ModeDisplay(Option<u32>);
 
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Chain {
     /// Builds a new empty signature chain.
     pub fn new() -> Self {
         Chain {
             num_snapshots: 0,
+            snapshot_times: Vec::new(),
             files: Vec::new(),
             ug_map: UserGroupMap::new(),
         }
@@ -157,6 +284,55 @@ impl Chain {
         Ok(chain)
     }
 
+    /// Opens a signature chain from a full signature stream.
+    ///
+    /// This allows to build a chain without a backend, e.g. from a downloaded file. The stream
+    /// must be gzip compressed if `compressed` is true, and `time` is the time of the full
+    /// snapshot. Incremental signatures can be added with `add_inc_sig_reader`.
+    pub fn from_full_sig_reader<R: Read>(
+        reader: R,
+        compressed: bool,
+        time: Timespec,
+    ) -> io::Result<Self> {
+        let mut chain = Chain::new();
+        chain.add_inc_sig_reader(reader, compressed, time)?;
+        Ok(chain)
+    }
+
+    /// Adds an incremental signature stream to the chain.
+    ///
+    /// The stream must be gzip compressed if `compressed` is true, and `time` is the time of the
+    /// incremental snapshot. Signatures must be added in chronological order. If an error occurs,
+    /// the chain is left unchanged.
+    ///
+    /// A chain can contain at most 255 snapshots: an error is returned when adding more.
+    pub fn add_inc_sig_reader<R: Read>(
+        &mut self,
+        reader: R,
+        compressed: bool,
+        time: Timespec,
+    ) -> io::Result<()> {
+        let compression = if compressed {
+            Compression::Gzip
+        } else {
+            Compression::None
+        };
+        self.add_signature(reader, compression, time)
+    }
+
+    /// Adds a new signature file to the chain.
+    ///
+    /// This allows to update a chain when a new incremental signature is available, without
+    /// reloading the whole chain from scratch. Signature files must be added in chronological
+    /// order, starting from the full signature. If an error occurs, the chain is left unchanged.
+    pub fn merge_incremental<R: Read>(
+        &mut self,
+        file: R,
+        sigfile: &SignatureFile,
+    ) -> io::Result<()> {
+        self.add_sigfile(file, sigfile)
+    }
+
     /// Returns the snapshots present in the signature chain.
     pub fn snapshots(&self) -> Snapshots {
         Snapshots {
@@ -165,21 +341,143 @@ impl Chain {
         }
     }
 
+    /// Returns an estimate of the memory used by this chain, in bytes.
+    pub fn approx_size_bytes(&self) -> usize {
+        let files = self
+            .files
+            .iter()
+            .map(|f| {
+                let links = f
+                    .snapshots
+                    .iter()
+                    .filter_map(|s| s.info.as_ref().and_then(|i| i.link.as_ref()))
+                    .map(|l| l.as_bytes().len())
+                    .sum::<usize>();
+                size_of::<PathSnapshots>()
+                    + f.path.as_bytes().len()
+                    + f.snapshots.len() * size_of::<PathSnapshot>()
+                    + links
+            })
+            .sum::<usize>();
+        let names = self
+            .ug_map
+            .uid_map
+            .values()
+            .chain(self.ug_map.gid_map.values())
+            .map(|n| size_of::<(u64, String)>() + n.len())
+            .sum::<usize>();
+        size_of::<Chain>() + files + names
+    }
+
+    /// Returns the time of the snapshot with the given index, if present in the chain.
+    ///
+    /// The time is the one of the signature file the snapshot has been loaded from.
+    pub fn snapshot_time(&self, index: u8) -> Option<Timespec> {
+        self.snapshot_times.get(index as usize).cloned()
+    }
+
+    /// Returns the paths of the entries of the given type, present in the given snapshot.
+    ///
+    /// This is faster than filtering the snapshot files, since no `Entry` is built. The paths are
+    /// represented as byte arrays, see `Entry::path_bytes`. If the snapshot is not present in the
+    /// chain, no path is returned.
+    pub fn paths_with_type(
+        &self,
+        entry_type: EntryType,
+        snapshot_id: u8,
+    ) -> impl Iterator<Item = &[u8]> {
+        let files: &[PathSnapshots] = if snapshot_id < self.num_snapshots {
+            &self.files
+        } else {
+            &[]
+        };
+        files.iter().filter_map(move |path_snapshots| {
+            path_snapshots
+                .info_at(snapshot_id)
+                .filter(|info| EntryType::new(info.entry_type) == entry_type)
+                .map(|_| path_snapshots.path.as_bytes())
+        })
+    }
+
+    /// Returns the index of the snapshot in which the given path has last changed, as seen by
+    /// the given snapshot.
+    ///
+    /// Returns `None` if the path is not present in the given snapshot.
+    pub fn last_change(&self, path: &[u8], snapshot_id: u8) -> Option<u8> {
+        self.files
+            .iter()
+            .find(|f| f.path.as_bytes() == path)?
+            .snapshots
+            .iter()
+            .rev()
+            .find(|s| s.index <= snapshot_id)
+            .filter(|s| s.info.is_some())
+            .map(|s| s.index)
+    }
+
+    /// Compares the entries of two snapshots of the chain.
+    ///
+    /// The snapshots are compared by using their signatures only, so changes in the content of a
+    /// file are detected through its metadata, e.g. modification time and size.
+    ///
+    /// # Panics
+    /// Panics if any of the two snapshots is not present in the chain.
+    pub fn diff_snapshots(&self, from: u8, to: u8) -> SnapshotDiff<'_> {
+        assert!(
+            from < self.num_snapshots && to < self.num_snapshots,
+            "snapshot not present in the chain"
+        );
+        SnapshotDiff {
+            from,
+            to,
+            iter: self.files.iter(),
+            chain: self,
+        }
+    }
+
+    /// Returns all the paths present in the chain, in any of its snapshots.
+    ///
+    /// Each path is returned once, sorted by path.
+    pub fn unique_paths(&self) -> impl Iterator<Item = &[u8]> {
+        self.files.iter().map(|f| f.path.as_bytes())
+    }
+
     fn add_sigfile<R: Read>(&mut self, file: R, sigfile: &SignatureFile) -> io::Result<()> {
+        let compression = if sigfile.compressed {
+            Compression::from_file_name(&sigfile.file_name)
+        } else {
+            Compression::None
+        };
+        self.add_signature(file, compression, sigfile.time)
+    }
+
+    fn add_signature<R: Read>(
+        &mut self,
+        file: R,
+        compression: Compression,
+        time: Timespec,
+    ) -> io::Result<()> {
+        // snapshot indexes are stored in a byte
+        if self.num_snapshots == u8::MAX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "too many snapshots in the chain, the maximum is {}",
+                    u8::MAX
+                ),
+            ));
+        }
         let result = {
             let snapshot_id = self.num_snapshots;
-            if sigfile.compressed {
-                let gz_decoder = GzDecoder::new(file);
-                self.add_sigtar_to_snapshots(tar::Archive::new(gz_decoder), snapshot_id)
-            } else {
-                self.add_sigtar_to_snapshots(tar::Archive::new(file), snapshot_id)
-            }
+            let decoder = compression.decoder(file)?;
+            self.add_sigtar_to_snapshots(tar::Archive::new(decoder), snapshot_id)
         };
         if result.is_ok() {
             // add to the list of snapshots only if everything is ok
             // we do not need to cleanup the chain if someting went wrong, because if the
             // number of signatures is not updated, the change is not observable
             self.num_snapshots += 1;
+            self.snapshot_times.push(time);
         }
         result
     }
@@ -199,9 +497,9 @@ impl Chain {
                 let mut tarfile = unwrap_or_continue!(tarfile);
                 let size_hint = compute_size_hint(&mut tarfile);
                 let path = &tarfile.path_bytes();
-                let (difftype, path) = unwrap_opt_or_continue!(parse_snapshot_path(&path));
-                let info = match difftype {
-                    DiffType::Signature | DiffType::Snapshot => {
+                let (kind, path, _) = unwrap_opt_or_continue!(pathparse::parse(path));
+                let info = match kind {
+                    PrefixKind::Signature | PrefixKind::Snapshot => {
                         let header = tarfile.header();
                         let time = Timespec::new(header.mtime().unwrap_or(0) as i64, 0);
                         if let (Ok(uid), Ok(Some(name))) = (header.uid(), header.username()) {
@@ -221,9 +519,12 @@ impl Chain {
                             size_hint: size_hint,
                             entry_type: tarfile.header().entry_type().as_byte(),
                             link: link,
+                            device: parse_device(header),
                         })
                     }
-                    _ => None,
+                    PrefixKind::Deleted => None,
+                    // not expected in signature archives
+                    _ => continue,
                 };
                 let new_snapshot = PathSnapshot {
                     info: info,
@@ -347,6 +648,31 @@ impl<'a> SnapshotEntries<'a> {
     pub fn into_display(self) -> SnapshotEntriesDisplay<'a> {
         SnapshotEntriesDisplay(self)
     }
+
+    /// Returns only the directories in the snapshot.
+    pub fn dirs(self) -> impl Iterator<Item = Entry<'a>> {
+        self.filter(|e| e.entry_type().is_dir())
+    }
+
+    /// Returns only the regular files in the snapshot.
+    ///
+    /// Directories, links and other special files are skipped.
+    pub fn files_only(self) -> impl Iterator<Item = Entry<'a>> {
+        self.filter(|e| e.entry_type().is_file())
+    }
+
+    /// Serializes the entries to a JSON array.
+    ///
+    /// Each entry is an object with the fields `path`, `type`, `size`, `mtime`, `mode`, `owner`
+    /// and `group`. The modification time is in RFC 3339 format, and the size is the upper bound
+    /// of `Entry::size_hint`. Paths that are not valid UTF-8 are converted lossily, and their
+    /// bytes are included in hexadecimal in the `path_hex` field.
+    #[cfg(feature = "serde")]
+    pub fn to_json(self) -> String {
+        let entries = self.map(|e| JsonEntry::from(&e)).collect::<Vec<_>>();
+        // serializing strings and numbers cannot fail
+        serde_json::to_string(&entries).unwrap()
+    }
 }
 
 impl<'a> Iterator for SnapshotEntries<'a> {
@@ -355,27 +681,126 @@ impl<'a> Iterator for SnapshotEntries<'a> {
     fn next(&mut self) -> Option<Entry<'a>> {
         let index = self.index; // prevents borrow checker complains
         for path_snapshots in &mut self.iter {
-            if let Some(s) = path_snapshots
-                .snapshots
-                .iter()
-                .rev()
-                .find(|s| s.index <= index)
-            {
-                // now we have a path info present in this snapshot
-                // if it is not deleted return it
-                if let Some(ref info) = s.info {
-                    return Some(Entry {
+            let snapshot = path_snapshots.snapshot_at(index);
+            if let Some((snapshot, info)) = snapshot.and_then(|s| s.info.as_ref().map(|i| (s, i))) {
+                return Some(Entry {
+                    path: &path_snapshots.path,
+                    info: info,
+                    ug_map: &self.chain.ug_map,
+                    changed: snapshot.index == index,
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for SnapshotDiff<'a> {
+    type Item = DiffEntry<'a>;
+
+    fn next(&mut self) -> Option<DiffEntry<'a>> {
+        let (from, to) = (self.from, self.to);
+        let ug_map = &self.chain.ug_map;
+        for path_snapshots in &mut self.iter {
+            let old_snapshot = path_snapshots.snapshot_at(from);
+            let new_snapshot = path_snapshots.snapshot_at(to);
+            let entry = |snapshot: Option<&'a PathSnapshot>, index| {
+                snapshot.and_then(|s| {
+                    s.info.as_ref().map(|info| Entry {
                         path: &path_snapshots.path,
-                        info: info,
-                        ug_map: &self.chain.ug_map,
-                    });
+                        info,
+                        ug_map,
+                        changed: s.index == index,
+                    })
+                })
+            };
+            let (old, new) = (entry(old_snapshot, from), entry(new_snapshot, to));
+            let kind = match (&old, &new) {
+                (None, None) => continue,
+                (None, Some(_)) => DiffKind::Added,
+                (Some(_), None) => DiffKind::Removed,
+                (Some(o), Some(n)) => {
+                    // the same snapshot of the path is seen by both
+                    let same = old_snapshot.map(|s| s.index) == new_snapshot.map(|s| s.index);
+                    if same || !entry_changed(o, n) {
+                        DiffKind::Unchanged
+                    } else {
+                        DiffKind::Modified
+                    }
                 }
-            }
+            };
+            return Some(DiffEntry { kind, old, new });
         }
         None
     }
 }
 
+impl<'a> DiffEntry<'a> {
+    /// Returns the path of the entry in bytes.
+    pub fn path_bytes(&self) -> &'a [u8] {
+        match (&self.new, &self.old) {
+            (Some(e), _) | (None, Some(e)) => e.path_bytes(),
+            // at least one of the entries is always present
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+impl<'a> From<&'a Chain> for Vec<SnapshotSummary> {
+    fn from(chain: &'a Chain) -> Self {
+        (0..chain.num_snapshots)
+            .map(|index| {
+                let mut summary = SnapshotSummary {
+                    time: chain.snapshot_times[index as usize],
+                    file_count: 0,
+                    total_size_hint_bytes: 0,
+                    num_added: 0,
+                    num_deleted: 0,
+                    num_modified: 0,
+                };
+                for path_snapshots in &chain.files {
+                    let current = path_snapshots.info_at(index);
+                    if let Some(info) = current {
+                        summary.file_count += 1;
+                        summary.total_size_hint_bytes +=
+                            info.size_hint.map_or(0, |(_, max)| max as u64);
+                    }
+                    if !path_snapshots.snapshots.iter().any(|s| s.index == index) {
+                        // the path has not been touched by this snapshot
+                        continue;
+                    }
+                    let previous = if index > 0 {
+                        path_snapshots.info_at(index - 1)
+                    } else {
+                        None
+                    };
+                    match (previous, current) {
+                        (None, Some(_)) => summary.num_added += 1,
+                        (Some(_), None) => summary.num_deleted += 1,
+                        (Some(_), Some(_)) => summary.num_modified += 1,
+                        (None, None) => (),
+                    }
+                }
+                summary
+            })
+            .collect()
+    }
+}
+
+impl PathSnapshots {
+    /// Returns the info of the path as seen by the given snapshot.
+    ///
+    /// Returns `None` if the path is not present or has been deleted in that snapshot.
+    fn info_at(&self, index: u8) -> Option<&PathInfo> {
+        self.snapshot_at(index).and_then(|s| s.info.as_ref())
+    }
+
+    /// Returns the last snapshot of the path not after the given snapshot.
+    fn snapshot_at(&self, index: u8) -> Option<&PathSnapshot> {
+        self.snapshots.iter().rev().find(|s| s.index <= index)
+    }
+}
+
 impl<'a> Display for SnapshotEntriesDisplay<'a> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         use std::io::Write;
@@ -405,6 +830,26 @@ impl<'a> Entry<'a> {
         self.path.as_bytes()
     }
 
+    /// Returns the path in which the entry should be restored, under the given destination.
+    ///
+    /// The root entry has an empty path, so the destination itself is returned for it. Under
+    /// Windows, paths not representable in UTF-8 are converted lossily.
+    pub fn restore_path(&self, dest: &Path) -> PathBuf {
+        let bytes = self.path_bytes();
+        if bytes.is_empty() {
+            return dest.to_path_buf();
+        }
+        match self.path() {
+            Some(path) => dest.join(path),
+            None => dest.join(String::from_utf8_lossy(bytes).as_ref()),
+        }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> EntryKey<'a> {
+        EntryKey(self.path.as_bytes())
+    }
+
     /// Returns the value of the owner's user ID field.
     pub fn userid(&self) -> Option<u64> {
         self.info.uid
@@ -420,6 +865,21 @@ impl<'a> Entry<'a> {
         self.info.mode
     }
 
+    /// Returns the permission bits of the file, as an octal number.
+    ///
+    /// Unlike `mode`, any bit besides the permissions and the special bits is cleared, e.g.
+    /// `0o755` for an executable file.
+    pub fn permissions_octal(&self) -> Option<u32> {
+        self.mode().map(|mode| mode & 0o7777)
+    }
+
+    /// Returns the type and the permissions of the entry in `ls -l` style, e.g. `drwxr-xr-x`.
+    ///
+    /// If the permissions are not known, they are represented by `?`.
+    pub fn permissions_string(&self) -> String {
+        format!("{}{}", self.entry_type(), ModeDisplay(self.mode()))
+    }
+
     /// Returns the name of the owner user.
     pub fn username(&self) -> Option<&'a str> {
         self.info.uid.and_then(|uid| self.ug_map.get_user_name(uid))
@@ -432,6 +892,18 @@ impl<'a> Entry<'a> {
             .and_then(|gid| self.ug_map.get_group_name(gid))
     }
 
+    /// Returns all the ownership information of the entry at once.
+    ///
+    /// This bundles `userid`, `groupid`, `username` and `groupname`.
+    pub fn ownership(&self) -> Ownership<'a> {
+        Ownership {
+            uid: self.userid(),
+            gid: self.groupid(),
+            uname: self.username(),
+            gname: self.groupname(),
+        }
+    }
+
     /// Returns the last modification time.
     pub fn mtime(&self) -> Timespec {
         self.info.mtime
@@ -456,15 +928,76 @@ impl<'a> Entry<'a> {
     pub fn linked_path(&self) -> Option<&'a Path> {
         self.info.link.as_ref().and_then(|p| p.as_path())
     }
+
+    /// Returns the major and minor device numbers of the entry.
+    ///
+    /// This will return some value only if this entry is a character or block device. Note that
+    /// tar headers do not carry inode numbers, so hard links cannot be grouped by inode.
+    pub fn device(&self) -> Option<(u32, u32)> {
+        self.info.device
+    }
+
+    /// Returns the metadata needed to restore the entry in the file system.
+    pub fn to_file_stat(&self) -> FileStat {
+        FileStat {
+            #[cfg(unix)]
+            uid: self.userid(),
+            #[cfg(unix)]
+            gid: self.groupid(),
+            mode: self.mode(),
+            mtime_sec: self.info.mtime.sec,
+            mtime_nsec: self.info.mtime.nsec,
+        }
+    }
+
+    /// Applies the owner, the permissions and the modification time of the entry to the file at
+    /// the given path.
+    ///
+    /// The file is never opened, so special files like FIFOs and devices are safe to update.
+    /// Symbolic links are not followed: only their owner and modification time are changed,
+    /// since their permissions are not meaningful. The access time is set to the modification
+    /// time. Changing the owner usually requires superuser privileges: in that case an error of
+    /// kind `PermissionDenied` is returned.
+    #[cfg(unix)]
+    pub fn apply_to_file(&self, path: &Path) -> io::Result<()> {
+        use filetime::{self, FileTime};
+        use std::convert::TryFrom;
+        use std::fs::{self, Permissions};
+        use std::os::unix::fs::{lchown, PermissionsExt};
+
+        let stat = self.to_file_stat();
+        // change the owner first, because it could clear the setuid and setgid bits
+        if stat.uid.is_some() || stat.gid.is_some() {
+            let to_id = |id: Option<u64>| {
+                id.map(u32::try_from)
+                    .transpose()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            };
+            lchown(path, to_id(stat.uid)?, to_id(stat.gid)?)?;
+        }
+        if let (Some(mode), false) = (stat.mode, self.entry_type().is_symlink()) {
+            fs::set_permissions(path, Permissions::from_mode(mode & 0o7777))?;
+        }
+        // set the times by path, without following symbolic links
+        let mtime = FileTime::from_unix_time(stat.mtime_sec, stat.mtime_nsec.max(0) as u32);
+        filetime::set_symlink_file_times(path, mtime, mtime)
+    }
+
+    /// Returns whether the entry has been added or modified in its snapshot.
+    ///
+    /// Returns `false` if the entry is unchanged from a previous snapshot of the chain. The
+    /// entries of a full snapshot are always changed.
+    pub fn changed_in_this_snapshot(&self) -> bool {
+        self.changed
+    }
 }
 
 impl<'a> Display for Entry<'a> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         write!(
             f,
-            "{}{}\t{}\t{}\t{}\t{}\t{}",
-            self.entry_type(),
-            ModeDisplay(self.mode()),
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.permissions_string(),
             self.username().unwrap_or("?"),
             self.groupname().unwrap_or("?"),
             self.size_hint()
@@ -477,6 +1010,41 @@ impl<'a> Display for Entry<'a> {
     }
 }
 
+impl<'a> EntryKey<'a> {
+    /// Returns the path of the entry in bytes.
+    pub fn path_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> From<&Entry<'a>> for JsonEntry<'a> {
+    fn from(e: &Entry<'a>) -> Self {
+        let bytes = e.path_bytes();
+        let path_hex = match std::str::from_utf8(bytes) {
+            Ok(_) => None,
+            Err(_) => Some(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+        };
+        JsonEntry {
+            path: String::from_utf8_lossy(bytes).into_owned(),
+            path_hex,
+            entry_type: match e.entry_type() {
+                EntryType::File => "file",
+                EntryType::Dir => "dir",
+                EntryType::HardLink => "hardlink",
+                EntryType::SymLink => "symlink",
+                EntryType::Fifo => "fifo",
+                EntryType::Unknown(_) => "unknown",
+            },
+            size: e.size_hint().map(|(_, upper)| upper),
+            mtime: e.mtime().into_sortable_display().to_string(),
+            mode: e.mode(),
+            owner: e.username(),
+            group: e.groupname(),
+        }
+    }
+}
+
 impl EntryType {
     /// Creates a new entry type from a raw byte.
     ///
@@ -491,6 +1059,21 @@ impl EntryType {
             _ => EntryType::Unknown(byte),
         }
     }
+
+    /// Returns whether this is a regular file.
+    pub fn is_file(self) -> bool {
+        self == EntryType::File
+    }
+
+    /// Returns whether this is a directory.
+    pub fn is_dir(self) -> bool {
+        self == EntryType::Dir
+    }
+
+    /// Returns whether this is a symbolic link.
+    pub fn is_symlink(self) -> bool {
+        self == EntryType::SymLink
+    }
 }
 
 impl Display for EntryType {
@@ -510,6 +1093,12 @@ impl Display for EntryType {
     }
 }
 
+impl Default for UserGroupMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserGroupMap {
     pub fn new() -> Self {
         UserGroupMap {
@@ -573,35 +1162,28 @@ impl Display for ModeDisplay {
     }
 }
 
-fn parse_snapshot_path(path: &[u8]) -> Option<(DiffType, &[u8])> {
-    // split the path in (first directory, the remaining path)
-    // the first is the type, the remaining is the real path
-    let pos = path.iter().cloned().position(|b| b == b'/')?;
-    let (pfirst, raw_real) = path.split_at(pos);
-    let difftype = match pfirst {
-        b"signature" => DiffType::Signature,
-        b"snapshot" => DiffType::Snapshot,
-        b"deleted" => DiffType::Deleted,
-        _ => {
-            return None;
+fn parse_device(header: &tar::Header) -> Option<(u32, u32)> {
+    // device numbers are meaningful only for device nodes
+    match header.entry_type() {
+        tar::EntryType::Char | tar::EntryType::Block => {
+            match (header.device_major(), header.device_minor()) {
+                (Ok(Some(major)), Ok(Some(minor))) => Some((major, minor)),
+                _ => None,
+            }
         }
-    };
-    let real = match raw_real.last().cloned() {
-        Some(b'/') if raw_real.len() > 1 => &raw_real[1..raw_real.len() - 1],
-        _ => &raw_real[1..],
-    };
-    Some((difftype, real))
+        _ => None,
+    }
 }
 
 fn compute_size_hint<R: Read>(file: &mut tar::Entry<R>) -> Option<(usize, usize)> {
-    let difftype = {
+    let kind = {
         let path = &file.path_bytes();
-        let (difftype, _) = parse_snapshot_path(&path)?;
-        difftype
+        let (kind, _, _) = pathparse::parse(path)?;
+        kind
     };
-    match difftype {
-        DiffType::Signature => compute_size_hint_signature(file),
-        DiffType::Snapshot => compute_size_hint_snapshot(file),
+    match kind {
+        PrefixKind::Signature => compute_size_hint_signature(file),
+        PrefixKind::Snapshot => compute_size_hint_snapshot(file),
         _ => None,
     }
 }
@@ -641,6 +1223,15 @@ fn compute_size_hint_snapshot<R: Read>(file: &mut tar::Entry<R>) -> Option<(usiz
     Some((bytes, bytes))
 }
 
+fn entry_changed(old: &Entry, new: &Entry) -> bool {
+    old.mtime() != new.mtime()
+        || old.size_hint() != new.size_hint()
+        || old.entry_type() != new.entry_type()
+        || old.mode() != new.mode()
+        || old.ownership() != new.ownership()
+        || old.linked_path() != new.linked_path()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -649,6 +1240,7 @@ mod test {
     use crate::collections::Collections;
     use crate::timefmt::parse_time_str;
 
+    use std::fs::File;
     use std::path::{Path, PathBuf};
     use time::Timespec;
 
@@ -825,12 +1417,29 @@ mod test {
     }
 
     fn single_vol_files() -> Chain {
-        let backend = LocalBackend::new("tests/backups/single_vol");
+        chain_files("tests/backups/single_vol")
+    }
+
+    fn chain_files(path: &str) -> Chain {
+        let backend = LocalBackend::new(path);
         let filenames = backend.file_names().unwrap();
         let coll = Collections::from_filenames(filenames);
         Chain::from_sigchain(coll.signature_chains().next().unwrap(), &backend).unwrap()
     }
 
+    fn check_file_list(files: &Chain) {
+        let expected_files = single_vol_expected_files();
+        let actual_files = files.snapshots().map(|s| {
+            s.files()
+                .map(|f| EntryTest::from_entry(&f))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(files.snapshots().count(), 3);
+        for (actual, expected) in actual_files.zip(expected_files) {
+            assert_eq!(actual, expected);
+        }
+    }
+
     fn single_vol_sizes() -> Vec<Vec<usize>> {
         // note that `ls -l` returns 4096 for directory size, but we consider directories to be
         // null sized.
@@ -849,19 +1458,171 @@ mod test {
 
     #[test]
     fn file_list() {
-        let expected_files = single_vol_expected_files();
+        check_file_list(&single_vol_files());
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn file_list_bzip2() {
+        check_file_list(&chain_files("tests/backups/bzip2"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn file_list_zstd() {
+        check_file_list(&chain_files("tests/backups/zstd"));
+    }
+
+    #[test]
+    fn file_list_prefixed() {
+        // open the backup from the parent directory
+        let backend = LocalBackend::new("tests/backups");
+        let filenames = LocalBackend::new("tests/backups/single_vol")
+            .file_names()
+            .unwrap()
+            .map(|f| Path::new("single_vol").join(f))
+            .collect::<Vec<_>>();
+        let coll = Collections::from_filenames(filenames);
+        let sigchain = coll.signature_chains().next().unwrap();
+        check_file_list(&Chain::from_sigchain(sigchain, &backend).unwrap());
+    }
+
+    #[test]
+    fn snapshot_time() {
         let files = single_vol_files();
-        // println!("debug files\n---------\n{:#?}\n----------", files);
-        let actual_files = files.snapshots().map(|s| {
-            s.files()
-                .map(|f| EntryTest::from_entry(&f))
+        let times = (0..4).map(|i| files.snapshot_time(i)).collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            vec![
+                parse_time_str("20150617t182545z"),
+                parse_time_str("20150617t182629z"),
+                parse_time_str("20150617t182650z"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_summary() {
+        let files = single_vol_files();
+        let summaries: Vec<SnapshotSummary> = (&files).into();
+        let actual = summaries
+            .iter()
+            .map(|s| (s.file_count, s.num_added, s.num_deleted, s.num_modified))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![(17, 17, 0, 0), (17, 2, 2, 8), (13, 0, 4, 6)]);
+        for (summary, snapshot) in summaries.iter().zip(files.snapshots()) {
+            let total: usize = snapshot.files().map(|f| f.size_hint().unwrap().1).sum();
+            assert_eq!(summary.total_size_hint_bytes, total as u64);
+        }
+        let times = summaries.iter().map(|s| Some(s.time)).collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            (0..3).map(|i| files.snapshot_time(i)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn ownership() {
+        let files = single_vol_files();
+        for snapshot in files.snapshots() {
+            for entry in snapshot.files() {
+                let ownership = entry.ownership();
+                assert_eq!(ownership.uid, entry.userid());
+                assert_eq!(ownership.gid, entry.groupid());
+                assert_eq!(ownership.uname, entry.username());
+                assert_eq!(ownership.gname, entry.groupname());
+            }
+        }
+        let first = files.snapshots().next().unwrap().files().next().unwrap();
+        assert_eq!(
+            first.ownership(),
+            Ownership {
+                uid: Some(1000),
+                gid: Some(1000),
+                uname: Some("michele"),
+                gname: Some("michele"),
+            }
+        );
+    }
+
+    #[test]
+    fn last_change() {
+        let files = single_vol_files();
+        let changes = |path: &[u8]| {
+            (0..3)
+                .map(|i| files.last_change(path, i))
                 .collect::<Vec<_>>()
-        });
-        assert_eq!(files.snapshots().count(), 3);
-        for (actual, expected) in actual_files.zip(expected_files) {
-            // println!("\nExpected:\n{:#?}\nActual:\n{:#?}", expected, actual);
-            assert_eq!(actual, expected);
+        };
+        assert_eq!(changes(b"regular_file"), vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(changes(b"new_file"), vec![None, Some(1), None]);
+        assert_eq!(changes(b"deleted_file"), vec![Some(0), None, None]);
+        assert_eq!(changes(b"not_existing"), vec![None, None, None]);
+    }
+
+    #[test]
+    fn default_empty() {
+        let chain = Chain::default();
+        assert_eq!(chain.snapshots().count(), 0);
+        assert_eq!(chain.approx_size_bytes(), Chain::new().approx_size_bytes());
+    }
+
+    #[test]
+    fn merge_incremental() {
+        let backend = LocalBackend::new("tests/backups/single_vol");
+        let coll = Collections::from_filenames(backend.file_names().unwrap());
+        let sigchain = coll.signature_chains().next().unwrap();
+        let open = |sigfile: &SignatureFile| backend.open_file(sigfile.file_name.as_ref()).unwrap();
+
+        let mut chain = Chain::new();
+        let full = sigchain.full_signature();
+        chain.merge_incremental(open(full), full).unwrap();
+        assert_eq!(chain.snapshots().count(), 1);
+        for inc in sigchain.inc_signatures() {
+            chain.merge_incremental(open(inc), inc).unwrap();
         }
+        check_file_list(&chain);
+    }
+
+    #[test]
+    fn from_readers() {
+        let dir = "tests/backups/single_vol";
+        let open = |name: &str| File::open(Path::new(dir).join(name)).unwrap();
+        let time = |s| parse_time_str(s).unwrap();
+        let mut chain = Chain::from_full_sig_reader(
+            open("duplicity-full-signatures.20150617T182545Z.sigtar.gz"),
+            true,
+            time("20150617t182545z"),
+        )
+        .unwrap();
+        chain
+            .add_inc_sig_reader(
+                open("duplicity-new-signatures.20150617T182545Z.to.20150617T182629Z.sigtar.gz"),
+                true,
+                time("20150617t182629z"),
+            )
+            .unwrap();
+        chain
+            .add_inc_sig_reader(
+                open("duplicity-new-signatures.20150617T182629Z.to.20150617T182650Z.sigtar.gz"),
+                true,
+                time("20150617t182650z"),
+            )
+            .unwrap();
+        check_file_list(&chain);
+        assert_eq!(chain.snapshot_time(2), Some(time("20150617t182650z")));
+
+        // uncompressed signature
+        let chain = Chain::from_full_sig_reader(
+            File::open(
+                "tests/backups/uncompressed/duplicity-full-signatures.20150617T182545Z.sigtar",
+            )
+            .unwrap(),
+            false,
+            time("20150617t182545z"),
+        )
+        .unwrap();
+        assert_eq!(chain.snapshots().count(), 1);
     }
 
     #[test]
@@ -891,6 +1652,49 @@ mod test {
         }
     }
 
+    #[test]
+    fn paths_with_type() {
+        let files = single_vol_files();
+        let dirs = files.paths_with_type(EntryType::Dir, 0).collect::<Vec<_>>();
+        assert_eq!(dirs, vec![&b""[..], &b"directory_to_file"[..]]);
+        let fifos = files.paths_with_type(EntryType::Fifo, 2).count();
+        assert_eq!(fifos, 1);
+        assert_eq!(files.paths_with_type(EntryType::Dir, 3).count(), 0);
+    }
+
+    #[test]
+    fn unique_paths() {
+        let files = single_vol_files();
+        let paths = files.unique_paths().collect::<Vec<_>>();
+        assert_eq!(paths.len(), 19);
+        assert!(paths.windows(2).all(|w| w[0] < w[1]));
+        // all the entries are present in the unique paths
+        for snapshot in files.snapshots() {
+            for entry in snapshot.files() {
+                assert!(paths.contains(&entry.key().path_bytes()));
+            }
+        }
+    }
+
+    #[test]
+    fn entry_key_order() {
+        let files = single_vol_files();
+        let snapshot = files.snapshots().next().unwrap();
+        let keys = snapshot.files().map(|e| e.key()).collect::<Vec<_>>();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn device() {
+        // there are no device nodes in the backup
+        let files = single_vol_files();
+        for snapshot in files.snapshots() {
+            assert!(snapshot.files().all(|f| f.device().is_none()));
+        }
+    }
+
     #[test]
     fn display() {
         // NOTE: this is actually not a proper test
@@ -905,6 +1709,325 @@ mod test {
         }
     }
 
+    #[test]
+    fn permissions() {
+        let files = single_vol_files();
+        let snapshot = files.snapshots().next().unwrap();
+        let actual = snapshot
+            .files()
+            .map(|f| (f.permissions_string(), f.permissions_octal()))
+            .take(3)
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ("drwxr-xr-x".to_owned(), Some(0o755)),
+            ("-rw-r--r--".to_owned(), Some(0o644)),
+            ("-rw-r--r--".to_owned(), Some(0o644)),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn restore_path() {
+        let files = single_vol_files();
+        let snapshot = files.snapshots().next().unwrap();
+        let dest = Path::new("/tmp/restore");
+        let actual = snapshot
+            .files()
+            .map(|f| f.restore_path(dest))
+            .collect::<Vec<_>>();
+        assert_eq!(actual[0], dest);
+        assert_eq!(actual[3], Path::new("/tmp/restore/directory_to_file"));
+        assert_eq!(actual[4], Path::new("/tmp/restore/directory_to_file/file"));
+        // non UTF-8 names are preserved
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            let last = snapshot.files().last().unwrap();
+            let name = actual.last().unwrap().strip_prefix(dest).unwrap();
+            assert_eq!(name.as_os_str().as_bytes(), last.path_bytes());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn to_json() {
+        let files = single_vol_files();
+        let json = files.snapshots().next().unwrap().files().to_json();
+        let entries: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let entries = entries.as_array().unwrap();
+        assert_eq!(
+            entries.len(),
+            files.snapshots().next().unwrap().files().count()
+        );
+        let entry = entries
+            .iter()
+            .find(|e| e["path"] == "regular_file")
+            .unwrap();
+        assert_eq!(entry["type"], "file");
+        assert_eq!(entry["size"], 75776);
+        assert_eq!(entry["mtime"], "2001-08-28T07:30:52Z");
+        assert_eq!(entry["mode"], 0o644);
+        assert_eq!(entry["owner"], "michele");
+        assert_eq!(entry["group"], "michele");
+        assert!(entry.get("path_hex").is_none());
+        // non UTF-8 paths have also the hex representation
+        let non_utf8 = entries
+            .iter()
+            .find(|e| e.get("path_hex").is_some())
+            .unwrap();
+        assert!(non_utf8["path_hex"]
+            .as_str()
+            .unwrap()
+            .starts_with("d8abb157"));
+    }
+
+    #[test]
+    fn changed_in_this_snapshot() {
+        let chain = chain_files("tests/backups/multi_chain");
+        assert!(chain
+            .snapshots()
+            .next()
+            .unwrap()
+            .files()
+            .all(|e| e.changed_in_this_snapshot()));
+        let summaries: Vec<SnapshotSummary> = (&chain).into();
+        for (id, snapshot) in chain.snapshots().enumerate().skip(1) {
+            let mut num_changed = 0;
+            for e in snapshot.files() {
+                let last_change = chain.last_change(e.path_bytes(), id as u8);
+                assert_eq!(e.changed_in_this_snapshot(), last_change == Some(id as u8));
+                if e.changed_in_this_snapshot() {
+                    num_changed += 1;
+                }
+            }
+            let summary = summaries[id];
+            assert_eq!(num_changed, summary.num_added + summary.num_modified);
+            assert!(num_changed > 0);
+        }
+    }
+
+    #[test]
+    fn file_stat() {
+        let files = single_vol_files();
+        let snapshot = files.snapshots().next().unwrap();
+        let entry = snapshot
+            .files()
+            .find(|e| e.path_bytes() == b"regular_file")
+            .unwrap();
+        let stat = entry.to_file_stat();
+        assert_eq!(stat.mode, entry.mode());
+        assert_eq!(stat.mtime_sec, entry.mtime().sec);
+        assert_eq!(stat.mtime_nsec, entry.mtime().nsec);
+        #[cfg(unix)]
+        assert_eq!((stat.uid, stat.gid), (entry.userid(), entry.groupid()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_to_file() {
+        use std::fs;
+        use std::os::unix::fs::{symlink, FileTypeExt, MetadataExt};
+        use std::process::Command;
+
+        let files = single_vol_files();
+        let snapshot = files.snapshots().next().unwrap();
+        let find = |path: &[u8]| snapshot.files().find(|e| e.path_bytes() == path).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let entry = find(b"regular_file");
+        let file_path = dir.path().join("regular_file");
+        File::create(&file_path).unwrap();
+        match entry.apply_to_file(&file_path) {
+            // not running as superuser
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+            result => result.unwrap(),
+        }
+        let meta = fs::metadata(&file_path).unwrap();
+        assert_eq!(meta.mode() & 0o7777, entry.permissions_octal().unwrap());
+        assert_eq!(meta.mtime(), entry.mtime().sec);
+        assert_eq!(Some(u64::from(meta.uid())), entry.userid());
+        assert_eq!(Some(u64::from(meta.gid())), entry.groupid());
+
+        // symbolic links are not followed, even if the target does not exist
+        let entry = find(b"symbolic_link");
+        let link_path = dir.path().join("symbolic_link");
+        symlink("missing", &link_path).unwrap();
+        entry.apply_to_file(&link_path).unwrap();
+        let meta = fs::symlink_metadata(&link_path).unwrap();
+        assert_eq!(Some(u64::from(meta.uid())), entry.userid());
+        assert_eq!(meta.mtime(), entry.mtime().sec);
+        assert!(!dir.path().join("missing").exists());
+
+        // a FIFO is not opened, so applying does not block waiting for a writer
+        let entry = find(b"fifo");
+        let fifo_path = dir.path().join("fifo");
+        let status = Command::new("mkfifo").arg(&fifo_path).status().unwrap();
+        assert!(status.success());
+        entry.apply_to_file(&fifo_path).unwrap();
+        let meta = fs::symlink_metadata(&fifo_path).unwrap();
+        assert!(meta.file_type().is_fifo());
+        assert_eq!(meta.mode() & 0o7777, entry.permissions_octal().unwrap());
+        assert_eq!(meta.mtime(), entry.mtime().sec);
+    }
+
+    #[test]
+    fn too_many_snapshots() {
+        // a signature archive containing a single file, modified at the given time
+        let sigtar = |mtime: u64| {
+            let mut header = tar::Header::new_gnu();
+            header.set_path("snapshot/file").unwrap();
+            header.set_size(0);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            let mut builder = tar::Builder::new(Vec::new());
+            builder.append(&header, &[][..]).unwrap();
+            builder.into_inner().unwrap()
+        };
+        let time = |t: u64| Timespec::new(t as i64, 0);
+
+        let mut chain = Chain::from_full_sig_reader(&sigtar(0)[..], false, time(0)).unwrap();
+        for t in 1..300 {
+            let result = chain.add_inc_sig_reader(&sigtar(t)[..], false, time(t));
+            assert_eq!(result.is_ok(), t < 255, "snapshot {}", t);
+        }
+        assert_eq!(chain.snapshots().count(), 255);
+        assert!(chain.snapshots().nth(255).is_none());
+        let last = chain.snapshots().last().unwrap();
+        assert_eq!(chain.snapshot_time(254), Some(time(254)));
+        let file = last.files().next().unwrap();
+        assert_eq!(file.path_bytes(), b"file");
+        assert_eq!(file.mtime(), time(254));
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        use super::DiffKind::*;
+
+        let chain = single_vol_files();
+        let changes = |from, to| {
+            chain
+                .diff_snapshots(from, to)
+                .filter(|d| d.kind != Unchanged)
+                .map(|d| (String::from_utf8_lossy(d.path_bytes()).into_owned(), d.kind))
+                .collect::<Vec<_>>()
+        };
+        let expected = |changes: &[(&str, DiffKind)]| {
+            changes
+                .iter()
+                .map(|&(p, k)| (p.to_owned(), k))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            changes(0, 1),
+            expected(&[
+                ("", Modified),
+                ("changeable_permission", Modified),
+                ("deleted_file", Removed),
+                ("directory_to_file", Modified),
+                ("directory_to_file/file", Removed),
+                ("executable2", Modified),
+                ("executable2/another_file", Added),
+                ("file_to_directory", Modified),
+                ("largefile", Modified),
+                ("new_file", Added),
+                ("regular_file", Modified),
+                ("symbolic_link", Modified),
+            ])
+        );
+        assert_eq!(
+            changes(1, 2),
+            expected(&[
+                ("", Modified),
+                ("changeable_permission", Modified),
+                ("directory_to_file", Removed),
+                ("executable2", Modified),
+                ("executable2/another_file", Removed),
+                ("file_to_directory", Removed),
+                ("largefile", Modified),
+                ("new_file", Removed),
+                ("regular_file", Modified),
+                ("symbolic_link", Modified),
+            ])
+        );
+        // changes reverted by the last snapshot are not reported
+        assert_eq!(
+            changes(0, 2),
+            expected(&[
+                ("deleted_file", Removed),
+                ("directory_to_file", Removed),
+                ("directory_to_file/file", Removed),
+                ("file_to_directory", Removed),
+                ("largefile", Modified),
+                ("symbolic_link", Modified),
+            ])
+        );
+        // swapping the snapshots swaps added and removed entries
+        let reversed = changes(2, 0)
+            .into_iter()
+            .map(|(p, k)| match k {
+                Added => (p, Removed),
+                Removed => (p, Added),
+                k => (p, k),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reversed, changes(0, 2));
+
+        for (id, snapshot) in chain.snapshots().enumerate() {
+            let id = id as u8;
+            // a snapshot has no changes from itself
+            assert!(changes(id, id).is_empty());
+            assert_eq!(
+                chain.diff_snapshots(id, id).count(),
+                snapshot.files().count()
+            );
+        }
+        // entries are reported as they are in the two snapshots
+        for diff in chain.diff_snapshots(0, 1) {
+            assert_eq!(diff.old.is_some(), diff.kind != Added);
+            assert_eq!(diff.new.is_some(), diff.kind != Removed);
+            if let Some(ref new) = diff.new {
+                assert_eq!(
+                    new.changed_in_this_snapshot(),
+                    chain.last_change(new.path_bytes(), 1) == Some(1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dirs_and_files() {
+        let files = single_vol_files();
+        let names = |entries: Vec<Entry>| {
+            entries
+                .iter()
+                .map(|e| String::from_utf8_lossy(e.path_bytes()).into_owned())
+                .collect::<Vec<_>>()
+        };
+        let dirs = files
+            .snapshots()
+            .map(|s| names(s.files().dirs().collect()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dirs,
+            vec![
+                vec!["", "directory_to_file"],
+                vec!["", "executable2", "file_to_directory", "symbolic_link"],
+                vec![""],
+            ]
+        );
+        let num_files = files
+            .snapshots()
+            .map(|s| s.files().files_only().count())
+            .collect::<Vec<_>>();
+        assert_eq!(num_files, vec![13, 12, 10]);
+        let snapshot = files.snapshots().nth(1).unwrap();
+        assert!(snapshot
+            .files()
+            .files_only()
+            .any(|e| e.path_bytes() == b"executable2/another_file"));
+    }
+
     #[test]
     fn mode_display() {
         fn mode_display(mode: Option<u32>) -> String {
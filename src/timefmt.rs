@@ -1,7 +1,7 @@
 //! Utilities to parse and display timestamps.
 //!
 //! This sub-module contains a trait that can be used to display a timestamp in local or UTC time
-//! zones, and a function to parse a timestamp.
+//! zones, and functions to parse timestamps.
 //!
 //! # Example
 //! Parse a duplicity timestamp and display it:
@@ -14,6 +14,7 @@
 //! ```
 
 use std::fmt::{Display, Formatter, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
 use time;
 use time::{Timespec, Tm};
 
@@ -28,6 +29,12 @@ pub trait TimeDisplay {
     fn into_utc_display(self) -> Self::D;
 }
 
+/// Trait that allows to display a time in a lexicographically sortable format.
+pub trait SortableTimeDisplay {
+    /// Turns self into a displayable type that is lexicographically sortable.
+    fn into_sortable_display(self) -> SortableDisplay;
+}
+
 /// Implements `Display` in a pretty style for some Tm instance.
 ///
 /// The format is `month day year` in case the timestamp is for a year different than the current;
@@ -39,16 +46,110 @@ pub trait TimeDisplay {
 #[derive(Copy, Clone, Debug)]
 pub struct PrettyDisplay(Tm);
 
+/// Implements `Display` in ISO 8601 format for some timestamp.
+///
+/// The timestamp is always displayed in the UTC time zone, so that the lexicographic order of the
+/// displayed strings is the same as the order of the timestamps.
+///
+/// # Example
+/// The timestamp `1988-12-11T15:20:00Z` is displayed as `1988-12-11T15:20:00Z`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct SortableDisplay(Timespec);
+
 /// Parse a string representing a duplicity timestamp and returns a `Timespec` if all goes well.
 ///
 /// An example of such a timestamp is "19881211t152000z" which represents the date
-/// `1988-12-11T15:20:00Z` in the UTC time zone.
+/// `1988-12-11T15:20:00Z` in the UTC time zone. Timestamps without the trailing `z`, like
+/// "19881211t152000", are accepted as well, and treated as UTC.
 pub fn parse_time_str(s: &str) -> Option<Timespec> {
     time::strptime(s, "%Y%m%dt%H%M%S%Z")
+        .or_else(|_| time::strptime(s, "%Y%m%dt%H%M%S"))
         .ok()
         .map(|tm| tm.to_timespec())
 }
 
+/// Parse a timestamp in duplicity format, accepting common variations of it.
+///
+/// This is useful for timestamps given by the user. Dashes and colons are ignored, a space or
+/// an underscore can be used instead of the `t` separator, the case is ignored and the trailing
+/// `z` is optional, so "2015-06-17T18:25:45Z", "2015-06-17 18:25:45" and "20150617T182545" are
+/// all accepted. The time is always considered UTC.
+pub fn parse_time_str_lenient(s: &str) -> Option<Timespec> {
+    let mut normalized = s
+        .trim()
+        .chars()
+        .filter(|&c| c != '-' && c != ':')
+        .map(|c| match c {
+            ' ' | '_' => 't',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect::<String>();
+    // time zone offsets are not supported
+    if !normalized
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == 't' || c == 'z')
+    {
+        return None;
+    }
+    if !normalized.ends_with('z') {
+        normalized.push('z');
+    }
+    parse_time_str(&normalized)
+}
+
+/// Parse a RFC 3339 timestamp and returns a `Timespec` if all goes well.
+///
+/// Examples of such timestamps are "2015-06-17T18:25:45Z" and "2015-06-17T20:25:45+02:00",
+/// that represent the same time. Fractional seconds are accepted, but ignored.
+pub fn parse_rfc3339(s: &str) -> Option<Timespec> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 20 || (bytes[10] != b'T' && bytes[10] != b't') || !s.is_char_boundary(19) {
+        return None;
+    }
+    let datetime = format!("{}T{}", &s[..10], &s[11..19]);
+    let tm = time::strptime(&datetime, "%Y-%m-%dT%H:%M:%S").ok()?;
+    let mut offset = &s[19..];
+    if offset.starts_with('.') {
+        let fraction_len = offset[1..].bytes().take_while(u8::is_ascii_digit).count();
+        if fraction_len == 0 {
+            return None;
+        }
+        offset = &offset[fraction_len + 1..];
+    }
+    let offset_secs = match offset.as_bytes() {
+        b"Z" | b"z" => 0,
+        &[sign, h1, h2, b':', m1, m2] if sign == b'+' || sign == b'-' => {
+            let hours = parse_two_digits(h1, h2).filter(|&h| h < 24)?;
+            let minutes = parse_two_digits(m1, m2).filter(|&m| m < 60)?;
+            let secs = hours * 3600 + minutes * 60;
+            if sign == b'+' {
+                secs
+            } else {
+                -secs
+            }
+        }
+        _ => return None,
+    };
+    Some(Timespec::new(tm.to_timespec().sec - offset_secs, 0))
+}
+
+fn parse_two_digits(d1: u8, d2: u8) -> Option<i64> {
+    if d1.is_ascii_digit() && d2.is_ascii_digit() {
+        Some(i64::from((d1 - b'0') * 10 + d2 - b'0'))
+    } else {
+        None
+    }
+}
+
+// serializes a time as seconds since the Unix epoch
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_timespec<S: serde::Serializer>(
+    time: &Timespec,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_i64(time.sec)
+}
+
 impl TimeDisplay for Timespec {
     type D = PrettyDisplay;
 
@@ -61,6 +162,46 @@ impl TimeDisplay for Timespec {
     }
 }
 
+impl SortableTimeDisplay for Timespec {
+    fn into_sortable_display(self) -> SortableDisplay {
+        SortableDisplay(self)
+    }
+}
+
+impl TimeDisplay for SystemTime {
+    type D = PrettyDisplay;
+
+    fn into_local_display(self) -> Self::D {
+        system_time_to_timespec(self).into_local_display()
+    }
+
+    fn into_utc_display(self) -> Self::D {
+        system_time_to_timespec(self).into_utc_display()
+    }
+}
+
+impl SortableTimeDisplay for SystemTime {
+    fn into_sortable_display(self) -> SortableDisplay {
+        system_time_to_timespec(self).into_sortable_display()
+    }
+}
+
+// converts a system time to a timespec, handling times before the Unix epoch as well
+fn system_time_to_timespec(t: SystemTime) -> Timespec {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => Timespec::new(d.as_secs() as i64, d.subsec_nanos() as i32),
+        Err(e) => {
+            let d = e.duration();
+            let mut ts = Timespec::new(-(d.as_secs() as i64), 0);
+            if d.subsec_nanos() > 0 {
+                ts.sec -= 1;
+                ts.nsec = 1_000_000_000 - d.subsec_nanos() as i32;
+            }
+            ts
+        }
+    }
+}
+
 impl Display for PrettyDisplay {
     fn fmt(&self, f: &mut Formatter) -> Result {
         if time::now_utc().tm_year == self.0.tm_year {
@@ -74,9 +215,17 @@ impl Display for PrettyDisplay {
     }
 }
 
+impl Display for SortableDisplay {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let tm = time::at_utc(self.0);
+        write!(f, "{}", time::strftime("%Y-%m-%dT%H:%M:%SZ", &tm).unwrap())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::time::Duration;
     use time::{self, Tm};
 
     fn time(y: i32, mon: i32, d: i32, h: i32, min: i32, s: i32) -> Tm {
@@ -123,6 +272,102 @@ mod test {
         assert_eq!(tm.tm_sec, 0);
     }
 
+    #[test]
+    fn parse_formats() {
+        let expected = time(1988, 12, 11, 15, 20, 0).to_timespec();
+        assert_eq!(parse_time_str("19881211t152000z"), Some(expected));
+        assert_eq!(parse_time_str("19881211t152000Z"), Some(expected));
+        assert_eq!(parse_time_str("19881211t152000"), Some(expected));
+        assert_eq!(parse_time_str("19881211t1520"), None);
+        assert_eq!(parse_time_str("19881211"), None);
+    }
+
+    #[test]
+    fn parse_lenient() {
+        let expected = parse_time_str("20150617t182545z");
+        assert!(expected.is_some());
+        for s in &[
+            "20150617t182545z",
+            "20150617T182545Z",
+            "20150617t182545Z",
+            "20150617T182545z",
+            "20150617t182545",
+            "20150617T182545",
+            "2015-06-17T18:25:45Z",
+            "2015-06-17t18:25:45z",
+            "2015-06-17T18:25:45",
+            "2015-06-17t18:25:45",
+            "2015-06-17 18:25:45",
+            "2015-06-17 18:25:45Z",
+            "2015-06-17_18:25:45",
+            "2015-06-17_18:25:45z",
+            "2015-0617T18:2545Z",
+            "201506-17T1825:45Z",
+            "20150617T18:25:45Z",
+            "2015-06-17T182545Z",
+            "20150617 182545",
+            "20150617 182545Z",
+            "20150617_182545",
+            "20150617_182545z",
+            " 2015-06-17T18:25:45Z ",
+            "\t20150617t182545z\n",
+            "2015--06--17T18::25::45Z",
+            "2015-06-17T18:25:45:Z",
+            "-2015-06-17T18:25:45Z-",
+            ":20150617t182545:",
+        ] {
+            assert_eq!(parse_time_str_lenient(s), expected, "{:?}", s);
+        }
+        for s in &[
+            "",
+            "z",
+            "garbage",
+            "2015-06-17",
+            "2015-06-17T18:25",
+            "2015/06/17 18:25:45",
+            "2015-06-17  18:25:45",
+            "2015-06-17T18:25:45+02:00",
+        ] {
+            assert_eq!(parse_time_str_lenient(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn parse_rfc3339_utc() {
+        let expected = parse_time_str("20150617t182545z");
+        assert_eq!(parse_rfc3339("2015-06-17T18:25:45Z"), expected);
+        assert_eq!(parse_rfc3339("2015-06-17t18:25:45z"), expected);
+        assert_eq!(parse_rfc3339("2015-06-17T18:25:45.123Z"), expected);
+        assert_eq!(parse_rfc3339("2015-06-17T18:25:45+00:00"), expected);
+    }
+
+    #[test]
+    fn parse_rfc3339_offset() {
+        let expected = parse_time_str("20150617t182545z");
+        assert_eq!(parse_rfc3339("2015-06-17T20:25:45+02:00"), expected);
+        assert_eq!(parse_rfc3339("2015-06-17T16:55:45-01:30"), expected);
+        assert_eq!(parse_rfc3339("2015-06-18T04:25:45.5+10:00"), expected);
+    }
+
+    #[test]
+    fn parse_rfc3339_invalid() {
+        for s in &[
+            "",
+            "garbage",
+            "2015-06-17",
+            "2015-06-17T18:25:45",
+            "2015-06-17 18:25:45Z",
+            "2015-06-17T18:25:45+0200",
+            "2015-06-17T18:25:45+24:00",
+            "2015-06-17T18:25:45.Z",
+            "2015-06-17T18:25:45Zjunk",
+            "2015-06-17T25:25:45Z",
+            "2015-06-17T18:25:45€",
+        ] {
+            assert_eq!(parse_rfc3339(s), None, "{}", s);
+        }
+    }
+
     #[test]
     fn display_utc() {
         let time = move_to_this_year(time(1988, 12, 11, 15, 20, 0));
@@ -170,4 +415,50 @@ mod test {
         let time = parse_time_str("19881211t152000z").unwrap();
         assert_eq!(format!("{}", time.into_utc_display()), "Dec 11  1988");
     }
+
+    #[test]
+    fn display_system_time() {
+        let secs = time(1988, 12, 11, 15, 20, 0).to_timespec().sec as u64;
+        let t = UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(format!("{}", t.into_utc_display()), "Dec 11  1988");
+        assert_eq!(
+            t.into_sortable_display().to_string(),
+            "1988-12-11T15:20:00Z"
+        );
+        let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+        assert_eq!(
+            system_time_to_timespec(before_epoch),
+            Timespec::new(-2, 500_000_000)
+        );
+    }
+
+    #[test]
+    fn display_sortable() {
+        let time = parse_time_str("19881211t152000z").unwrap();
+        assert_eq!(
+            time.into_sortable_display().to_string(),
+            "1988-12-11T15:20:00Z"
+        );
+    }
+
+    #[test]
+    fn sortable_order() {
+        let mut times = [
+            parse_time_str("20150617t182545z").unwrap(),
+            parse_time_str("19881211t152000z").unwrap(),
+            parse_time_str("20150617t090000z").unwrap(),
+            parse_time_str("20010101t000000z").unwrap(),
+        ];
+        let mut strings = times
+            .iter()
+            .map(|t| t.into_sortable_display().to_string())
+            .collect::<Vec<_>>();
+        times.sort();
+        strings.sort();
+        let expected = times
+            .iter()
+            .map(|t| t.into_sortable_display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(strings, expected);
+    }
 }